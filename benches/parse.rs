@@ -0,0 +1,25 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use distro::{OsRelease, OsReleaseRef};
+
+const FEDORA: &str = include_str!("../fedora-rawhide-os-release");
+
+fn parse(c: &mut Criterion) {
+    c.bench_function("from_iter fedora-rawhide", |b| {
+        b.iter(|| OsRelease::from_iter(black_box(FEDORA).lines().map(String::from)))
+    });
+
+    c.bench_function("from_bytes fedora-rawhide", |b| {
+        b.iter(|| OsRelease::from_bytes(black_box(FEDORA.as_bytes())).unwrap())
+    });
+
+    c.bench_function("OsReleaseRef::parse fedora-rawhide", |b| {
+        b.iter(|| OsReleaseRef::parse(black_box(FEDORA)))
+    });
+
+    c.bench_function("from_reader_strict fedora-rawhide", |b| {
+        b.iter(|| OsRelease::from_reader_strict(black_box(FEDORA.as_bytes())).unwrap())
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
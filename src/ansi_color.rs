@@ -0,0 +1,415 @@
+//! Presenting the OS name in the color suggested by `ANSI_COLOR`.
+//!
+//! `ANSI_COLOR` holds the parameters of an SGR escape sequence, such as
+//! `0;38;2;60;110;180`. Distributions favor 24-bit colors, which are converted to
+//! the nearest of the xterm 256-color or 16-color palettes for terminals that
+//! cannot show them.
+
+use crate::OsRelease;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// The colors that a terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSupport {
+    /// No colors or other styling.
+    None,
+    /// The 16 standard and bright colors.
+    Ansi16,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// 24-bit colors.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// The colors supported by the terminal, according to the environment.
+    ///
+    /// A non-empty `NO_COLOR` disables color, as described at <https://no-color.org>.
+    /// Otherwise `COLORTERM` and `TERM` are consulted. Whether the output is a
+    /// terminal at all is left to the caller.
+    pub fn detect() -> ColorSupport {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    /// The colors supported by a terminal with the given `COLORTERM` and `TERM`,
+    /// regardless of `NO_COLOR`.
+    pub fn from_term(colorterm: Option<&str>, term: Option<&str>) -> ColorSupport {
+        match (colorterm, term.unwrap_or_default()) {
+            (Some("truecolor" | "24bit"), _) => ColorSupport::TrueColor,
+            (_, "" | "dumb") => ColorSupport::None,
+            (_, term) if term.contains("256color") => ColorSupport::Ansi256,
+            _ => ColorSupport::Ansi16,
+        }
+    }
+
+    fn detect_with<F: Fn(&str) -> Option<String>>(var: F) -> ColorSupport {
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::None;
+        }
+        Self::from_term(var("COLORTERM").as_deref(), var("TERM").as_deref())
+    }
+}
+
+/// A color given by its index in the xterm palette, or by its red, green and blue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// A color of the xterm 256-color palette, set with `38;5;N`.
+    Indexed(u8),
+    /// A 24-bit color, set with `38;2;R;G;B`.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The red, green and blue of the color, taking the default xterm palette.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Indexed(i @ 0..=15) => PALETTE_16[usize::from(i)],
+            Color::Indexed(i @ 16..=231) => {
+                let i = usize::from(i - 16);
+                (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+            }
+            Color::Indexed(i) => {
+                let gray = 8 + 10 * (i - 232);
+                (gray, gray, gray)
+            }
+        }
+    }
+
+    /// The nearest color of the xterm 256-color palette.
+    pub fn to_ansi256(self) -> u8 {
+        let (r, g, b) = match self {
+            Color::Indexed(i) => return i,
+            Color::Rgb(r, g, b) => (r, g, b),
+        };
+
+        let level = |c: u8| match c {
+            0..=47 => 0,
+            48..=114 => 1,
+            c => usize::from((c - 35) / 40),
+        };
+        let (ri, gi, bi) = (level(r), level(g), level(b));
+        let cube = (16 + 36 * ri + 6 * gi + bi) as u8;
+
+        let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+        let gray = 232 + ((average.max(3) - 3) / 10).min(23) as u8;
+
+        [cube, gray]
+            .into_iter()
+            .min_by_key(|&i| distance((r, g, b), Color::Indexed(i).to_rgb()))
+            .expect("there are candidates")
+    }
+
+    /// The nearest of the 16 standard and bright colors, from 0 to 15.
+    pub fn to_ansi16(self) -> u8 {
+        if let Color::Indexed(i @ 0..=15) = self {
+            return i;
+        }
+        let rgb = self.to_rgb();
+        (0..16)
+            .min_by_key(|&i| distance(rgb, PALETTE_16[usize::from(i)]))
+            .expect("the palette is not empty")
+    }
+}
+
+/// The channel levels of the 6x6x6 color cube of the xterm palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The default colors of xterm for the 16 standard and bright colors.
+const PALETTE_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let square = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+    square(a.0, b.0) + square(a.1, b.1) + square(a.2, b.2)
+}
+
+/// A single attribute set by an SGR sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Attribute {
+    /// A parameter that stands alone, such as `1` for bold or `34` for blue.
+    Code(u8),
+    /// An extended color for the foreground (`38`), background (`48`) or
+    /// underline (`58`).
+    Color(u8, Color),
+}
+
+/// A validated `ANSI_COLOR`, the parameters of an SGR escape sequence.
+///
+/// **IE:** `0;38;2;60;110;180`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnsiColor {
+    attributes: Vec<Attribute>,
+}
+
+impl AnsiColor {
+    /// The extended foreground color, if one is set.
+    pub fn foreground(&self) -> Option<Color> {
+        self.attributes
+            .iter()
+            .rev()
+            .find_map(|attribute| match attribute {
+                Attribute::Color(38, color) => Some(*color),
+                _ => None,
+            })
+    }
+
+    /// The escape sequence that applies this color on a terminal with `support`,
+    /// or `None` if the terminal has no colors.
+    ///
+    /// Extended colors are converted to the nearest color the terminal can show.
+    pub fn escape(&self, support: ColorSupport) -> Option<String> {
+        let mut params = Vec::with_capacity(self.attributes.len());
+        for attribute in &self.attributes {
+            match (*attribute, support) {
+                (_, ColorSupport::None) => return None,
+                (Attribute::Code(code), _) => params.push(code.to_string()),
+                (Attribute::Color(target, color), ColorSupport::TrueColor) => {
+                    params.push(extended(target, color))
+                }
+                (Attribute::Color(target, color), ColorSupport::Ansi256) => {
+                    params.push(extended(target, Color::Indexed(color.to_ansi256())))
+                }
+                // There is no standard code for the underline color.
+                (Attribute::Color(58, _), ColorSupport::Ansi16) => (),
+                (Attribute::Color(target, color), ColorSupport::Ansi16) => {
+                    let base = if target == 38 { 30 } else { 40 };
+                    params.push(match color.to_ansi16() {
+                        i @ 0..=7 => (base + i).to_string(),
+                        i => (base + 60 + i - 8).to_string(),
+                    })
+                }
+            }
+        }
+
+        Some(format!("\x1b[{}m", params.join(";")))
+    }
+
+    /// `text` in this color, followed by a reset, or `text` alone if the terminal
+    /// has no colors.
+    pub fn paint(&self, text: &str, support: ColorSupport) -> String {
+        match self.escape(support) {
+            Some(escape) => format!("{}{}\x1b[0m", escape, text),
+            None => text.to_owned(),
+        }
+    }
+}
+
+fn extended(target: u8, color: Color) -> String {
+    match color {
+        Color::Indexed(i) => format!("{};5;{}", target, i),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", target, r, g, b),
+    }
+}
+
+impl FromStr for AnsiColor {
+    type Err = ParseAnsiColorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseAnsiColorError(text.to_owned());
+        let params = text
+            .split(';')
+            .map(|param| match param.len() {
+                1..=3 if param.bytes().all(|byte| byte.is_ascii_digit()) => {
+                    param.parse::<u8>().map_err(|_| invalid())
+                }
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        let mut attributes = Vec::new();
+        let mut params = params.into_iter();
+        while let Some(param) = params.next() {
+            let attribute = match param {
+                target @ (38 | 48 | 58) => {
+                    let color = match params.next() {
+                        Some(5) => Color::Indexed(params.next().ok_or_else(invalid)?),
+                        Some(2) => match (params.next(), params.next(), params.next()) {
+                            (Some(r), Some(g), Some(b)) => Color::Rgb(r, g, b),
+                            _ => return Err(invalid()),
+                        },
+                        _ => return Err(invalid()),
+                    };
+                    Attribute::Color(target, color)
+                }
+                code @ 0..=107 => Attribute::Code(code),
+                _ => return Err(invalid()),
+            };
+            attributes.push(attribute);
+        }
+
+        Ok(AnsiColor { attributes })
+    }
+}
+
+impl fmt::Display for AnsiColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, attribute) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            match *attribute {
+                Attribute::Code(code) => write!(f, "{}", code)?,
+                Attribute::Color(target, color) => f.write_str(&extended(target, color))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when parsing a string that is not a valid `ANSI_COLOR`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseAnsiColorError(String);
+
+impl fmt::Display for ParseAnsiColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a valid SGR sequence", self.0)
+    }
+}
+
+impl std::error::Error for ParseAnsiColorError {}
+
+impl OsRelease {
+    /// The parsed `ANSI_COLOR`.
+    ///
+    /// Returns `None` if `ANSI_COLOR` is absent or is not a valid SGR sequence.
+    pub fn name_color(&self) -> Option<AnsiColor> {
+        self.ansi_color.as_deref()?.parse().ok()
+    }
+
+    /// `PRETTY_NAME`, or `Linux` if it is not set, in the color of `ANSI_COLOR`.
+    pub fn render_pretty_name(&self, support: ColorSupport) -> String {
+        let pretty_name = self.pretty_name.as_deref().unwrap_or("Linux");
+        match self.name_color() {
+            Some(color) => color.paint(pretty_name, support),
+            None => pretty_name.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn color(text: &str) -> AnsiColor {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let fedora = color("0;38;2;60;110;180");
+        assert_eq!(fedora.foreground(), Some(Color::Rgb(60, 110, 180)));
+        assert_eq!(fedora.to_string(), "0;38;2;60;110;180");
+        assert_eq!(color("1;34").foreground(), None);
+        assert_eq!(color("38;5;33").foreground(), Some(Color::Indexed(33)));
+
+        for invalid in [
+            "", "0;", ";1", "x", "0;38", "38;2;1;2", "38;5", "38;3;1", "108", "0;256", "0001",
+            "\x1b[0m",
+        ] {
+            assert!(invalid.parse::<AnsiColor>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn degrade() {
+        let fedora = color("0;38;2;60;110;180");
+        assert_eq!(
+            fedora.escape(ColorSupport::TrueColor).unwrap(),
+            "\x1b[0;38;2;60;110;180m"
+        );
+        assert_eq!(
+            fedora.escape(ColorSupport::Ansi256).unwrap(),
+            "\x1b[0;38;5;61m"
+        );
+        assert_eq!(fedora.escape(ColorSupport::Ansi16).unwrap(), "\x1b[0;94m");
+        assert_eq!(fedora.escape(ColorSupport::None), None);
+
+        assert_eq!(Color::Rgb(128, 128, 128).to_ansi256(), 244);
+        assert_eq!(Color::Rgb(255, 0, 0).to_ansi256(), 196);
+        assert_eq!(Color::Indexed(196).to_ansi16(), 9);
+        assert_eq!(
+            color("48;5;2").escape(ColorSupport::Ansi16).unwrap(),
+            "\x1b[42m"
+        );
+        assert_eq!(
+            color("1;58;5;9").escape(ColorSupport::Ansi16).unwrap(),
+            "\x1b[1m"
+        );
+        assert_eq!(Color::Rgb(0, 0, 0).to_ansi256(), 16);
+        for i in 16..=255 {
+            let (r, g, b) = Color::Indexed(i).to_rgb();
+            assert_eq!(Color::Rgb(r, g, b).to_ansi256(), i);
+        }
+    }
+
+    #[test]
+    fn detect() {
+        let detect = |vars: &[(&str, &str)]| {
+            ColorSupport::detect_with(|name| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        let truecolor = [("COLORTERM", "truecolor"), ("TERM", "xterm-256color")];
+        assert_eq!(detect(&truecolor), ColorSupport::TrueColor);
+        assert_eq!(detect(&truecolor[1..]), ColorSupport::Ansi256);
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorSupport::Ansi16);
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorSupport::None);
+        assert_eq!(detect(&[]), ColorSupport::None);
+        assert_eq!(
+            detect(&[("NO_COLOR", "1"), truecolor[0]]),
+            ColorSupport::None
+        );
+        assert_eq!(
+            detect(&[("NO_COLOR", ""), truecolor[0]]),
+            ColorSupport::TrueColor
+        );
+    }
+
+    #[test]
+    fn render_pretty_name() {
+        let os_release = OsRelease::from_iter(
+            [
+                "PRETTY_NAME=\"Fedora Linux 38\"",
+                "ANSI_COLOR=\"0;38;2;60;110;180\"",
+            ]
+            .into_iter()
+            .map(String::from),
+        );
+        assert_eq!(
+            os_release.render_pretty_name(ColorSupport::Ansi16),
+            "\x1b[0;94mFedora Linux 38\x1b[0m"
+        );
+        assert_eq!(
+            os_release.render_pretty_name(ColorSupport::None),
+            "Fedora Linux 38"
+        );
+
+        let invalid = OsRelease {
+            ansi_color: Some("bold".into()),
+            ..OsRelease::default()
+        };
+        assert_eq!(invalid.name_color(), None);
+        assert_eq!(invalid.render_pretty_name(ColorSupport::TrueColor), "Linux");
+    }
+}
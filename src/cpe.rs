@@ -0,0 +1,603 @@
+//! Common Platform Enumeration names, as given by `CPE_NAME`.
+//!
+//! Both bindings of CPE names are understood: the CPE 2.2 URI, such as
+//! `cpe:/o:fedoraproject:fedora:38`, and the CPE 2.3 formatted string, such as
+//! `cpe:2.3:o:fedoraproject:fedora:38:*:*:*:*:*:*:*`. Either can be parsed and
+//! written back out as the other, following NISTIR 7695.
+
+use crate::OsRelease;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// The kind of platform that a CPE name identifies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Part {
+    /// An application, bound as `a`.
+    Application,
+    /// A hardware device, bound as `h`.
+    Hardware,
+    /// An operating system, bound as `o`.
+    OperatingSystem,
+}
+
+impl Part {
+    fn as_char(self) -> char {
+        match self {
+            Part::Application => 'a',
+            Part::Hardware => 'h',
+            Part::OperatingSystem => 'o',
+        }
+    }
+}
+
+/// The value of a single attribute of a CPE name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Component {
+    /// Any value, bound as `*` in a formatted string and left empty in a URI.
+    Any,
+    /// No value applies, bound as `-`.
+    NotApplicable,
+    /// A value, with any escaping of its binding removed.
+    ///
+    /// Values are made of printable ASCII characters other than space, which is all
+    /// that either binding can hold. Parsing rejects anything else, and a value built
+    /// by hand that breaks this rule is written out as is, which will not parse.
+    ///
+    /// **IE:** `fedoraproject`
+    Value(String),
+}
+
+impl Component {
+    /// The value, if this is `Component::Value`.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Component::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Component::Any
+    }
+}
+
+/// A CPE name, such as `cpe:/o:fedoraproject:fedora:38`.
+///
+/// The CPE 2.3 formatted string is used by `Display` and serde, and `FromStr`
+/// accepts either binding. CPE 2.2 URIs are case-insensitive, so their values are
+/// lowercased. Names identify a single platform, so wildcards within values are
+/// rejected.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cpe {
+    /// **IE:** `Part::OperatingSystem`
+    pub part: Part,
+    /// **IE:** `fedoraproject`
+    pub vendor: Component,
+    /// **IE:** `fedora`
+    pub product: Component,
+    /// **IE:** `38`
+    pub version: Component,
+    /// The update or service pack of the version.
+    ///
+    /// **IE:** `sp1`, in `cpe:/o:microsoft:windows_7::sp1`
+    pub update: Component,
+    /// The legacy edition attribute of CPE 2.2.
+    ///
+    /// **IE:** `baseos`, in `cpe:/o:redhat:enterprise_linux:9::baseos`
+    pub edition: Component,
+    /// The language of the user interface, as an RFC 5646 tag.
+    ///
+    /// **IE:** `en-us`
+    pub language: Component,
+    /// How the product is tailored to a market or class of users.
+    ///
+    /// **IE:** `online`
+    pub sw_edition: Component,
+    /// The software environment that the product runs in.
+    ///
+    /// **IE:** `win2003`
+    pub target_sw: Component,
+    /// The instruction set architecture that the product runs on.
+    ///
+    /// **IE:** `x64`
+    pub target_hw: Component,
+    /// Any other information, as a vendor- or product-specific value.
+    pub other: Component,
+}
+
+impl Cpe {
+    /// The name with `part`, `vendor`, `product` and `version`, and every other
+    /// attribute set to `Component::Any`.
+    ///
+    /// The values must be printable ASCII, as described at `Component::Value`.
+    pub fn new(part: Part, vendor: &str, product: &str, version: &str) -> Cpe {
+        Cpe {
+            part,
+            vendor: Component::Value(vendor.to_owned()),
+            product: Component::Value(product.to_owned()),
+            version: Component::Value(version.to_owned()),
+            update: Component::Any,
+            edition: Component::Any,
+            language: Component::Any,
+            sw_edition: Component::Any,
+            target_sw: Component::Any,
+            target_hw: Component::Any,
+            other: Component::Any,
+        }
+    }
+
+    /// Parse a CPE 2.2 URI, such as `cpe:/o:fedoraproject:fedora:38`.
+    ///
+    /// The attributes added by CPE 2.3 are unpacked from an edition of the form
+    /// `~edition~sw_edition~target_sw~target_hw~other`.
+    pub fn from_uri(uri: &str) -> Result<Cpe, ParseCpeError> {
+        let error = |reason| ParseCpeError {
+            name: uri.to_owned(),
+            reason,
+        };
+        let lowercase = uri.to_ascii_lowercase();
+        let rest = lowercase
+            .strip_prefix("cpe:/")
+            .ok_or(error("a CPE 2.2 URI starts with `cpe:/`"))?;
+
+        let fields: Vec<&str> = rest.split(':').collect();
+        if fields.len() > 7 {
+            return Err(error("a CPE 2.2 URI has at most 7 components"));
+        }
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+        let component = |text: &str| decode_uri(text).map_err(error);
+
+        let (edition, extended) = match field(5).strip_prefix('~') {
+            Some(packed) => {
+                let packed: Vec<&str> = packed.split('~').collect();
+                let [edition, sw_edition, target_sw, target_hw, other] = packed[..] else {
+                    return Err(error("a packed edition has 5 attributes separated by `~`"));
+                };
+                (edition, [sw_edition, target_sw, target_hw, other])
+            }
+            None => (field(5), [""; 4]),
+        };
+        let [sw_edition, target_sw, target_hw, other] = extended;
+
+        Ok(Cpe {
+            part: parse_part(field(0)).map_err(error)?,
+            vendor: component(field(1))?,
+            product: component(field(2))?,
+            version: component(field(3))?,
+            update: component(field(4))?,
+            edition: component(edition)?,
+            language: component(field(6))?,
+            sw_edition: component(sw_edition)?,
+            target_sw: component(target_sw)?,
+            target_hw: component(target_hw)?,
+            other: component(other)?,
+        })
+    }
+
+    /// Parse a CPE 2.3 formatted string, such as
+    /// `cpe:2.3:o:fedoraproject:fedora:38:*:*:*:*:*:*:*`.
+    pub fn from_formatted_string(text: &str) -> Result<Cpe, ParseCpeError> {
+        let error = |reason| ParseCpeError {
+            name: text.to_owned(),
+            reason,
+        };
+        let rest = match text.get(..8) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("cpe:2.3:") => &text[8..],
+            _ => return Err(error("a CPE 2.3 formatted string starts with `cpe:2.3:`")),
+        };
+
+        // Split on the colons that are not escaped.
+        let mut fields = Vec::with_capacity(11);
+        let mut start = 0;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                ':' => {
+                    fields.push(&rest[start..i]);
+                    start = i + 1;
+                }
+                _ => (),
+            }
+        }
+        fields.push(&rest[start..]);
+
+        if fields.len() != 11 {
+            return Err(error("a CPE 2.3 formatted string has 11 attributes"));
+        }
+        let component = |i: usize| unescape_fs(fields[i]).map_err(error);
+
+        Ok(Cpe {
+            part: parse_part(fields[0]).map_err(error)?,
+            vendor: component(1)?,
+            product: component(2)?,
+            version: component(3)?,
+            update: component(4)?,
+            edition: component(5)?,
+            language: component(6)?,
+            sw_edition: component(7)?,
+            target_sw: component(8)?,
+            target_hw: component(9)?,
+            other: component(10)?,
+        })
+    }
+
+    /// Bind this name as a CPE 2.2 URI.
+    ///
+    /// Attributes added by CPE 2.3 are packed into the edition, and trailing empty
+    /// components are left out.
+    pub fn to_uri(&self) -> String {
+        let extended = [
+            &self.sw_edition,
+            &self.target_sw,
+            &self.target_hw,
+            &self.other,
+        ];
+        let edition = match extended.iter().all(|component| component.is_any()) {
+            true => encode_uri(&self.edition),
+            false => std::iter::once(&self.edition)
+                .chain(extended)
+                .fold(String::new(), |packed, component| {
+                    packed + "~" + &encode_uri(component)
+                }),
+        };
+
+        let mut uri = format!(
+            "cpe:/{}:{}:{}:{}:{}:{}:{}",
+            self.part.as_char(),
+            encode_uri(&self.vendor),
+            encode_uri(&self.product),
+            encode_uri(&self.version),
+            encode_uri(&self.update),
+            edition,
+            encode_uri(&self.language),
+        );
+        uri.truncate(uri.trim_end_matches(':').len());
+        uri
+    }
+
+    /// Bind this name as a CPE 2.3 formatted string.
+    pub fn to_formatted_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FromStr for Cpe {
+    type Err = ParseCpeError;
+
+    /// Parse either a CPE 2.3 formatted string or a CPE 2.2 URI.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.get(..8) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("cpe:2.3:") => {
+                Cpe::from_formatted_string(text)
+            }
+            _ => Cpe::from_uri(text),
+        }
+    }
+}
+
+impl fmt::Display for Cpe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cpe:2.3:{}", self.part.as_char())?;
+        for component in [
+            &self.vendor,
+            &self.product,
+            &self.version,
+            &self.update,
+            &self.edition,
+            &self.language,
+            &self.sw_edition,
+            &self.target_sw,
+            &self.target_hw,
+            &self.other,
+        ] {
+            f.write_char(':')?;
+            match component {
+                Component::Any => f.write_char('*')?,
+                Component::NotApplicable => f.write_char('-')?,
+                Component::Value(value) if value == "-" => f.write_str("\\-")?,
+                Component::Value(value) => {
+                    for c in value.chars() {
+                        if !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+                            f.write_char('\\')?;
+                        }
+                        f.write_char(c)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cpe {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cpe {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_part(text: &str) -> Result<Part, &'static str> {
+    match text {
+        "a" => Ok(Part::Application),
+        "h" => Ok(Part::Hardware),
+        "o" => Ok(Part::OperatingSystem),
+        _ => Err("the part must be `a`, `h` or `o`"),
+    }
+}
+
+/// Decode a component of a CPE 2.2 URI, which percent-encodes punctuation.
+fn decode_uri(text: &str) -> Result<Component, &'static str> {
+    match text {
+        "" => return Ok(Component::Any),
+        "-" => return Ok(Component::NotApplicable),
+        _ => (),
+    }
+
+    let mut value = String::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'%' {
+            value.push(graphic(byte as char)?);
+            continue;
+        }
+
+        let hex = rest
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or("`%` must be followed by two hexadecimal digits")?;
+        match hex {
+            0x01 | 0x02 => return Err("a CPE name cannot contain wildcards"),
+            _ => value.push(graphic(hex as char)?),
+        }
+        rest = &rest[2..];
+    }
+
+    Ok(Component::Value(value))
+}
+
+/// Accept `c` if a CPE value can hold it.
+fn graphic(c: char) -> Result<char, &'static str> {
+    match c.is_ascii_graphic() {
+        true => Ok(c),
+        false => Err("a CPE name can only contain printable ASCII characters"),
+    }
+}
+
+/// Encode a component of a CPE 2.2 URI.
+fn encode_uri(component: &Component) -> String {
+    let value = match component {
+        Component::Any => return String::new(),
+        Component::NotApplicable => return "-".into(),
+        Component::Value(value) if value == "-" => return "%2d".into(),
+        Component::Value(value) => value,
+    };
+
+    let mut uri = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            c if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') => uri.push(c),
+            c => {
+                let mut utf8 = [0; 4];
+                for byte in c.encode_utf8(&mut utf8).bytes() {
+                    let _ = write!(uri, "%{:02x}", byte);
+                }
+            }
+        }
+    }
+    uri
+}
+
+/// Remove the escaping from a component of a CPE 2.3 formatted string.
+fn unescape_fs(text: &str) -> Result<Component, &'static str> {
+    match text {
+        "" => return Err("attributes of a CPE 2.3 formatted string cannot be empty"),
+        "*" => return Ok(Component::Any),
+        "-" => return Ok(Component::NotApplicable),
+        _ => (),
+    }
+
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(graphic(
+                chars.next().ok_or("`\\` must be followed by a character")?,
+            )?),
+            '*' | '?' => return Err("a CPE name cannot contain wildcards"),
+            c => value.push(graphic(c)?),
+        }
+    }
+    Ok(Component::Value(value))
+}
+
+/// The error returned when parsing a string that is not a CPE name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCpeError {
+    name: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseCpeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a valid CPE name: {}",
+            self.name, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseCpeError {}
+
+impl OsRelease {
+    /// The parsed `CPE_NAME`.
+    ///
+    /// Returns `None` if `CPE_NAME` is absent or is not a CPE name.
+    pub fn cpe(&self) -> Option<Cpe> {
+        self.cpe_name.as_deref()?.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::strategy::Strategy;
+
+    fn cpe(text: &str) -> Cpe {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn fedora() {
+        let os_release = OsRelease::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fedora-rawhide-os-release"
+        ))
+        .unwrap();
+        let fedora = os_release.cpe().unwrap();
+        assert_eq!(
+            fedora,
+            Cpe::new(Part::OperatingSystem, "fedoraproject", "fedora", "38")
+        );
+        assert_eq!(fedora.vendor.value(), Some("fedoraproject"));
+        assert_eq!(
+            fedora.to_string(),
+            "cpe:2.3:o:fedoraproject:fedora:38:*:*:*:*:*:*:*"
+        );
+        assert_eq!(fedora.to_uri(), "cpe:/o:fedoraproject:fedora:38");
+    }
+
+    #[test]
+    fn bindings() {
+        let rhel = cpe("cpe:/o:redhat:enterprise_linux:9::baseos");
+        assert_eq!(rhel.update, Component::Any);
+        assert_eq!(rhel.edition.value(), Some("baseos"));
+        assert_eq!(
+            rhel.to_formatted_string(),
+            "cpe:2.3:o:redhat:enterprise_linux:9:*:baseos:*:*:*:*:*"
+        );
+        assert_eq!(
+            cpe(&rhel.to_formatted_string()).to_uri(),
+            "cpe:/o:redhat:enterprise_linux:9::baseos"
+        );
+
+        // Examples from NISTIR 7695.
+        let hp = cpe("cpe:2.3:a:hp:insight_diagnostics:7.4.0.1570:-:*:*:online:win2003:x64:*");
+        assert_eq!(hp.update, Component::NotApplicable);
+        assert_eq!(
+            hp.to_uri(),
+            "cpe:/a:hp:insight_diagnostics:7.4.0.1570:-:~~online~win2003~x64~"
+        );
+        assert_eq!(cpe(&hp.to_uri()), hp);
+
+        let escaped = cpe(r"cpe:2.3:a:foo\\bar:big\$money_2010:*:*:*:*:special:ipod_touch:80gb:*");
+        assert_eq!(escaped.vendor.value(), Some(r"foo\bar"));
+        assert_eq!(escaped.product.value(), Some("big$money_2010"));
+        assert_eq!(
+            escaped.to_uri(),
+            "cpe:/a:foo%5cbar:big%24money_2010:::~~special~ipod_touch~80gb~"
+        );
+        assert_eq!(cpe(&escaped.to_uri()), escaped);
+
+        assert_eq!(
+            cpe("CPE:/O:Alpine:Alpine_Linux:3.19"),
+            cpe("cpe:/o:alpine:alpine_linux:3.19")
+        );
+        assert_eq!(
+            cpe(r"cpe:2.3:a:x:y:\-:*:*:*:*:*:*:*").to_uri(),
+            "cpe:/a:x:y:%2d"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        for invalid in [
+            "",
+            "cpe:",
+            "cpe:/",
+            "cpe:/x:vendor",
+            "cpe:/o:a:b:c:d:e:f:g",
+            "cpe:/o:a:b:1%2",
+            "cpe:/o:a:b:1%02",
+            "cpe:/o:a:b:c:d:~e~f",
+            "cpe:2.3:o:a:b:c",
+            "cpe:2.3:o:a:b:1.*:*:*:*:*:*:*:*",
+            "cpe:2.3:o:a::1:*:*:*:*:*:*:*",
+            "cpe:2.3:o:a:b:1:*:*:*:*:*:*:*:*",
+            r"cpe:2.3:o:a:b:1:*:*:*:*:*:*:\",
+            "cpe:/a:café:b",
+            "cpe:/a:caf%c3%a9:b",
+            "cpe:/a:big%20money:b",
+            "cpe:2.3:a:café:b:*:*:*:*:*:*:*:*",
+            r"cpe:2.3:a:caf\é:b:*:*:*:*:*:*:*:*",
+            "cpe:2.3:a:big money:b:*:*:*:*:*:*:*:*",
+        ] {
+            assert!(invalid.parse::<Cpe>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            "cpe:/x".parse::<Cpe>().unwrap_err().to_string(),
+            "`cpe:/x` is not a valid CPE name: the part must be `a`, `h` or `o`"
+        );
+        assert_eq!(OsRelease::default().cpe(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let fedora = cpe("cpe:/o:fedoraproject:fedora:38");
+        let json = serde_json::to_string(&fedora).unwrap();
+        assert_eq!(json, r#""cpe:2.3:o:fedoraproject:fedora:38:*:*:*:*:*:*:*""#);
+        assert_eq!(serde_json::from_str::<Cpe>(&json).unwrap(), fedora);
+    }
+
+    const PARTS: [Part; 3] = [Part::Application, Part::Hardware, Part::OperatingSystem];
+
+    fn component() -> impl proptest::strategy::Strategy<Value = Component> {
+        proptest::prop_oneof![
+            proptest::strategy::Just(Component::Any),
+            proptest::strategy::Just(Component::NotApplicable),
+            "[a-z0-9._~!$%&:*?\\\\-]{1,8}".prop_map(Component::Value),
+        ]
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn round_trip(
+            part in proptest::sample::select(&PARTS[..]),
+            components in proptest::collection::vec(component(), 10),
+        ) {
+            let mut components = components.into_iter();
+            let mut next = || components.next().unwrap();
+            let cpe = Cpe {
+                part,
+                vendor: next(),
+                product: next(),
+                version: next(),
+                update: next(),
+                edition: next(),
+                language: next(),
+                sw_edition: next(),
+                target_sw: next(),
+                target_hw: next(),
+                other: next(),
+            };
+            proptest::prop_assert_eq!(&Cpe::from_uri(&cpe.to_uri()).unwrap(), &cpe);
+            proptest::prop_assert_eq!(&cpe.to_string().parse::<Cpe>().unwrap(), &cpe);
+        }
+    }
+}
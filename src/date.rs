@@ -0,0 +1,272 @@
+use crate::OsRelease;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date, as used by `SUPPORT_END`.
+///
+/// Dates are ordered chronologically, and written out as `YYYY-MM-DD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// The date with the given `year`, `month` (1 to 12) and `day` (from 1), if it exists.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        (1..=days_in_month).contains(&day).then_some(Date {
+            year,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+
+    /// The current date in UTC.
+    pub fn today() -> Date {
+        let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        Date::from_days(seconds.div_euclid(86_400))
+    }
+
+    /// The year, such as 2024.
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    /// The month, from 1 for January to 12 for December.
+    pub fn month(self) -> u32 {
+        self.month.into()
+    }
+
+    /// The day of the month, from 1.
+    pub fn day(self) -> u32 {
+        self.day.into()
+    }
+
+    /// The number of days from this date until `other`, which is negative if `other`
+    /// comes first.
+    pub fn days_until(self, other: Date) -> i64 {
+        other.days() - self.days()
+    }
+
+    /// The day after this one.
+    pub(crate) fn next_day(self) -> Date {
+        Date::from_days(self.days() + 1)
+    }
+
+    /// Days since 1970-01-01, following the proleptic Gregorian calendar.
+    fn days(self) -> i64 {
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The inverse of `Date::days`.
+    fn from_days(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Date {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = ParseDateError;
+
+    /// Parse a date in the `YYYY-MM-DD` form.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseDateError(text.to_owned());
+        let number = |digits: &str, len| match digits.len() == len
+            && digits.bytes().all(|byte| byte.is_ascii_digit())
+        {
+            true => digits.parse().map_err(|_| invalid()),
+            false => Err(invalid()),
+        };
+
+        let mut fields = text.split('-');
+        let (Some(year), Some(month), Some(day), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid());
+        };
+
+        let year = number(year, 4)?;
+        Date::new(year as i32, number(month, 2)?, number(day, 2)?).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Date {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Date {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The error returned when parsing a string that is not a `YYYY-MM-DD` date.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseDateError(String);
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a valid YYYY-MM-DD date", self.0)
+    }
+}
+
+impl std::error::Error for ParseDateError {}
+
+impl OsRelease {
+    /// The parsed `SUPPORT_END`, the day on which support for this release ends.
+    ///
+    /// As in os-release(5), this is when security updates stop, so the release is
+    /// no longer supported from this day on.
+    ///
+    /// Returns `None` if `SUPPORT_END` is absent or is not a `YYYY-MM-DD` date.
+    pub fn support_end_date(&self) -> Option<Date> {
+        self.support_end.as_deref()?.parse().ok()
+    }
+
+    /// Whether support for this release has ended as of `date`, or `None` if the end
+    /// of support is not known.
+    pub fn is_past_support_end(&self, date: Date) -> Option<bool> {
+        Some(date >= self.support_end_date()?)
+    }
+
+    /// The number of days from `date` until support for this release ends, or `None`
+    /// if the end of support is not known.
+    ///
+    /// This is 0 on the day support ends, and so is positive only while the release
+    /// is still supported.
+    pub fn days_until_support_end(&self, date: Date) -> Option<i64> {
+        Some(date.days_until(self.support_end_date()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn d(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Date::new(2024, 5, 14), Some(d("2024-05-14")));
+        assert_eq!(d("2024-02-29").to_string(), "2024-02-29");
+        for invalid in [
+            "",
+            "2024-5-14",
+            "24-05-14",
+            "2024-05-14T00:00",
+            "2024/05/14",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-04-31",
+            "+024-04-01",
+        ] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+        assert_eq!(Date::new(2000, 2, 29).map(Date::day), Some(29));
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(d("1970-01-01").days(), 0);
+        assert_eq!(d("2000-03-01").days(), 11_017);
+        assert_eq!(d("1969-12-31").days(), -1);
+        for date in [
+            "1970-01-01",
+            "2000-02-29",
+            "2024-12-31",
+            "1601-01-01",
+            "2400-03-01",
+        ] {
+            assert_eq!(Date::from_days(d(date).days()), d(date));
+        }
+
+        assert_eq!(d("2023-12-31").days_until(d("2024-03-01")), 61);
+        assert_eq!(d("2024-03-01").days_until(d("2023-12-31")), -61);
+        assert!(d("2023-12-31") < d("2024-01-01"));
+        assert_eq!(d("2023-12-31").next_day(), d("2024-01-01"));
+        assert_eq!(d("2024-02-28").next_day(), d("2024-02-29"));
+        assert!(Date::today() > d("2023-01-01"));
+    }
+
+    #[test]
+    fn support_end() {
+        let os_release: OsRelease = "ID=fedora\nSUPPORT_END=2024-05-14\n".parse().unwrap();
+        assert_eq!(os_release.support_end_date(), Some(d("2024-05-14")));
+        assert_eq!(os_release.is_past_support_end(d("2024-05-13")), Some(false));
+        assert_eq!(os_release.is_past_support_end(d("2024-05-14")), Some(true));
+        assert_eq!(os_release.is_past_support_end(d("2024-05-15")), Some(true));
+        assert_eq!(os_release.days_until_support_end(d("2024-05-04")), Some(10));
+        assert_eq!(os_release.days_until_support_end(d("2024-05-14")), Some(0));
+        assert_eq!(os_release.days_until_support_end(d("2024-05-15")), Some(-1));
+
+        let unknown = OsRelease::default();
+        assert_eq!(unknown.is_past_support_end(d("2024-05-15")), None);
+        let invalid: OsRelease = "SUPPORT_END=soon\n".parse().unwrap();
+        assert_eq!(invalid.support_end_date(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        assert_eq!(
+            serde_json::to_string(&d("2024-05-14")).unwrap(),
+            r#""2024-05-14""#
+        );
+        let parsed: Date = serde_json::from_str(r#""2024-05-14""#).unwrap();
+        assert_eq!(parsed, d("2024-05-14"));
+        assert!(serde_json::from_str::<Date>(r#""tomorrow""#).is_err());
+    }
+}
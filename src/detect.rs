@@ -0,0 +1,441 @@
+//! Identification of systems that may predate os-release, through the release
+//! files that distributions shipped before it.
+
+use crate::lsb_release::LsbRelease;
+use crate::root;
+use crate::OsRelease;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The kind of file that a `Detection` was made from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// `/etc/os-release` or `/usr/lib/os-release`.
+    OsRelease,
+    /// `/etc/lsb-release`, as written by Ubuntu and its derivatives.
+    LsbRelease,
+    /// `/etc/centos-release`.
+    CentOsRelease,
+    /// `/etc/fedora-release`.
+    FedoraRelease,
+    /// `/etc/redhat-release`, also written by RHEL rebuilds.
+    RedHatRelease,
+    /// `/etc/SuSE-release`.
+    SuseRelease,
+    /// `/etc/debian_version`.
+    DebianVersion,
+    /// `/etc/alpine-release`.
+    AlpineRelease,
+    /// `/etc/arch-release`.
+    ArchRelease,
+    /// `/etc/gentoo-release`.
+    GentooRelease,
+    /// `/etc/slackware-version`.
+    SlackwareVersion,
+}
+
+/// Legacy release files, in the order they are consulted.
+///
+/// Files of derivatives come before those of the distributions they derive from,
+/// as derivatives often keep their parent's file too: CentOS ships a
+/// `redhat-release`, and Ubuntu a `debian_version`.
+const LEGACY_FILES: [(&str, Source, Synthesize); 10] = [
+    ("etc/lsb-release", Source::LsbRelease, from_lsb_release),
+    (
+        "etc/centos-release",
+        Source::CentOsRelease,
+        from_release_file,
+    ),
+    (
+        "etc/fedora-release",
+        Source::FedoraRelease,
+        from_release_file,
+    ),
+    (
+        "etc/redhat-release",
+        Source::RedHatRelease,
+        from_release_file,
+    ),
+    ("etc/SuSE-release", Source::SuseRelease, from_suse_release),
+    (
+        "etc/alpine-release",
+        Source::AlpineRelease,
+        from_alpine_release,
+    ),
+    ("etc/arch-release", Source::ArchRelease, from_arch_release),
+    (
+        "etc/gentoo-release",
+        Source::GentooRelease,
+        from_release_file,
+    ),
+    (
+        "etc/slackware-version",
+        Source::SlackwareVersion,
+        from_slackware_version,
+    ),
+    (
+        "etc/debian_version",
+        Source::DebianVersion,
+        from_debian_version,
+    ),
+];
+
+/// Converts the contents of a legacy release file into os-release data, or returns
+/// `None` if it cannot be understood.
+type Synthesize = fn(&str) -> Option<OsRelease>;
+
+/// The identity of a system, and where it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    /// The os-release data, synthesized from the legacy file if there was no os-release.
+    pub os_release: OsRelease,
+    /// The kind of file the data came from.
+    pub source: Source,
+    /// The file the data came from, including the root prefix.
+    pub path: PathBuf,
+}
+
+/// Identify the running system.
+pub fn detect() -> io::Result<Detection> {
+    detect_in("/")
+}
+
+/// Identify the system whose root directory is `root`.
+///
+/// os-release is used if it exists. Otherwise the legacy release files are tried in
+/// turn, and the first one that can be read and understood is converted into an
+/// `OsRelease`. Symlinks are resolved within `root`.
+pub fn detect_in<P: AsRef<Path>>(root: P) -> io::Result<Detection> {
+    let root = root.as_ref();
+    match OsRelease::discover_in(root) {
+        Ok((path, os_release)) => {
+            return Ok(Detection {
+                os_release,
+                source: Source::OsRelease,
+                path,
+            })
+        }
+        Err(why) if why.kind() == io::ErrorKind::NotFound => (),
+        Err(why) => return Err(why),
+    }
+
+    for (path, source, synthesize) in LEGACY_FILES {
+        // A file that cannot be read, or is not UTF-8, is passed over like one that
+        // cannot be understood.
+        let contents = match root::resolve(root, path).and_then(fs::read_to_string) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        if let Some(os_release) = synthesize(&contents) {
+            return Ok(Detection {
+                os_release,
+                source,
+                path: root.join(path),
+            });
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "neither os-release nor a legacy release file was found",
+    ))
+}
+
+/// The first line of `contents`, trimmed.
+fn first_line(contents: &str) -> &str {
+    contents.lines().next().unwrap_or_default().trim()
+}
+
+/// Parse a file whose first line is of the form `NAME release VERSION (CODENAME)`.
+fn from_release_file(contents: &str) -> Option<OsRelease> {
+    from_release_line(first_line(contents))
+}
+
+/// Parse `/etc/debian_version`, which holds either a version or a codename such as
+/// `bookworm/sid`.
+fn from_debian_version(contents: &str) -> Option<OsRelease> {
+    let first_line = first_line(contents);
+    let mut os_release = named("debian", "Debian GNU/Linux");
+    if first_line.starts_with(|c: char| c.is_ascii_digit()) {
+        os_release.version_id = Some(first_line.to_owned());
+    } else if let Some(codename) = first_line.split('/').next().filter(|c| !c.is_empty()) {
+        os_release.version_codename = Some(codename.to_owned());
+    }
+    Some(with_pretty_name(os_release, first_line))
+}
+
+/// Parse `/etc/alpine-release`, which holds the version.
+fn from_alpine_release(contents: &str) -> Option<OsRelease> {
+    let first_line = first_line(contents);
+    let mut os_release = named("alpine", "Alpine Linux");
+    os_release.version_id = Some(first_line.to_owned()).filter(|v| !v.is_empty());
+    Some(with_pretty_name(os_release, first_line))
+}
+
+/// `/etc/arch-release` is empty, as Arch Linux is not versioned.
+fn from_arch_release(_: &str) -> Option<OsRelease> {
+    Some(with_pretty_name(named("arch", "Arch Linux"), ""))
+}
+
+/// Parse `/etc/slackware-version`, as in `Slackware 15.0`.
+fn from_slackware_version(contents: &str) -> Option<OsRelease> {
+    let version = first_line(contents).strip_prefix("Slackware")?.trim();
+    let mut os_release = named("slackware", "Slackware");
+    os_release.version_id = Some(version.to_owned()).filter(|v| !v.is_empty());
+    Some(with_pretty_name(os_release, version))
+}
+
+/// Map the `DISTRIB_*` keys of `/etc/lsb-release`.
+fn from_lsb_release(contents: &str) -> Option<OsRelease> {
+    let lsb_release = LsbRelease::parse(contents);
+    lsb_release
+        .distrib_id
+        .as_ref()
+        .filter(|id| !id.is_empty())?;
+    Some(lsb_release.to_os_release())
+}
+
+/// Parse a line of the form `NAME release VERSION (CODENAME)`, as used by Red Hat
+/// and its derivatives, and by Gentoo.
+fn from_release_line(line: &str) -> Option<OsRelease> {
+    let (name, rest) = line.split_once(" release ")?;
+    let (version, codename) = match rest.split_once(' ') {
+        Some((version, codename)) => (version, Some(codename.trim())),
+        None => (rest, None),
+    };
+    let codename = codename
+        .and_then(|codename| codename.strip_prefix('(')?.strip_suffix(')'))
+        .filter(|codename| !codename.is_empty());
+
+    let fallback_id;
+    let (id, id_like, short_name) = if name.starts_with("Red Hat Enterprise Linux") {
+        ("rhel", &["fedora"][..], "Red Hat Enterprise Linux")
+    } else if name.starts_with("CentOS") {
+        ("centos", &["rhel", "fedora"][..], name)
+    } else if name.starts_with("Rocky Linux") {
+        ("rocky", &["rhel", "centos", "fedora"][..], name)
+    } else if name.starts_with("AlmaLinux") {
+        ("almalinux", &["rhel", "centos", "fedora"][..], name)
+    } else if name.starts_with("Oracle Linux") {
+        ("ol", &["fedora"][..], "Oracle Linux Server")
+    } else if name.starts_with("Fedora") {
+        ("fedora", &[][..], "Fedora")
+    } else if name.starts_with("Gentoo") {
+        ("gentoo", &[][..], "Gentoo")
+    } else {
+        fallback_id = name.split_whitespace().next()?.to_lowercase();
+        (fallback_id.as_str(), &[][..], name)
+    };
+
+    // CentOS gives its full version here, such as `7.9.2009`, but only the major
+    // version in the `VERSION_ID` of its os-release, so only that is kept.
+    let version_id = match id {
+        "centos" => version.split('.').next().unwrap_or(version),
+        _ => version,
+    };
+
+    let mut os_release = named(id, short_name);
+    os_release.id_like = id_like.iter().map(|id| id.to_string()).collect();
+    os_release.version_id = Some(version_id.to_owned());
+    os_release.version = Some(rest.trim().to_owned());
+    os_release.version_codename = codename.map(String::from);
+    os_release.pretty_name = Some(line.to_owned());
+    Some(os_release)
+}
+
+/// Parse `/etc/SuSE-release`, which names the release on its first line and
+/// follows it with `VERSION = 11` and `PATCHLEVEL = 4` lines.
+fn from_suse_release(contents: &str) -> Option<OsRelease> {
+    let mut lines = contents.lines();
+    let title = lines.next()?.trim();
+    let field = |key: &str| {
+        contents.lines().skip(1).find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            Some(value.trim()).filter(|_| name.trim() == key)
+        })
+    };
+
+    let (id, name) = if title.starts_with("openSUSE") {
+        ("opensuse", "openSUSE")
+    } else {
+        ("sles", "SLES")
+    };
+
+    let mut os_release = named(id, name);
+    os_release.id_like = vec!["suse".into()];
+    os_release.version_id = field("VERSION").map(|version| match field("PATCHLEVEL") {
+        Some(patchlevel) if patchlevel != "0" => format!("{}.{}", version, patchlevel),
+        _ => version.to_owned(),
+    });
+    os_release.version_codename = field("CODENAME").map(String::from);
+    // Drop the architecture that follows the name, as in `openSUSE 13.2 (x86_64)`.
+    let pretty_name = title.rsplit_once(" (").map_or(title, |(name, _)| name);
+    os_release.pretty_name = Some(pretty_name.to_owned());
+    Some(os_release)
+}
+
+fn named(id: &str, name: &str) -> OsRelease {
+    OsRelease {
+        id: Some(id.to_owned()),
+        name: Some(name.to_owned()),
+        ..OsRelease::default()
+    }
+}
+
+/// Fill in `PRETTY_NAME` as the name followed by `version`, if there is one.
+fn with_pretty_name(mut os_release: OsRelease, version: &str) -> OsRelease {
+    let name = os_release.name.as_deref().unwrap_or_default();
+    os_release.pretty_name = Some(match version {
+        "" => name.to_owned(),
+        version => format!("{} {}", name, version),
+    });
+    os_release
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for (path, contents) in files {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    fn detect(files: &[(&str, &str)]) -> Detection {
+        let root = fixture(files);
+        let detection = detect_in(root.path()).unwrap();
+        assert!(detection.path.starts_with(root.path()));
+        detection
+    }
+
+    #[test]
+    fn prefers_os_release() {
+        let detection = detect(&[
+            ("usr/lib/os-release", "ID=fedora\n"),
+            ("etc/redhat-release", "Fedora release 38 (Thirty Eight)\n"),
+        ]);
+        assert_eq!(detection.source, Source::OsRelease);
+        assert_eq!(detection.os_release.id.as_deref(), Some("fedora"));
+    }
+
+    #[test]
+    fn lsb_release() {
+        let detection = detect(&[
+            (
+                "etc/lsb-release",
+                "DISTRIB_ID=Ubuntu\nDISTRIB_RELEASE=12.04\nDISTRIB_CODENAME=precise\n\
+                 DISTRIB_DESCRIPTION=\"Ubuntu 12.04.5 LTS\"\n",
+            ),
+            ("etc/debian_version", "wheezy/sid\n"),
+        ]);
+        assert_eq!(detection.source, Source::LsbRelease);
+        let os_release = detection.os_release;
+        assert_eq!(os_release.id.as_deref(), Some("ubuntu"));
+        assert_eq!(os_release.name.as_deref(), Some("Ubuntu"));
+        assert_eq!(os_release.version_id.as_deref(), Some("12.04"));
+        assert_eq!(os_release.version_codename.as_deref(), Some("precise"));
+        assert_eq!(
+            os_release.pretty_name.as_deref(),
+            Some("Ubuntu 12.04.5 LTS")
+        );
+    }
+
+    #[test]
+    fn lsb_release_without_id_falls_through() {
+        let detection = detect(&[
+            ("etc/lsb-release", "LSB_VERSION=base-4.0-amd64\n"),
+            (
+                "etc/redhat-release",
+                "Red Hat Enterprise Linux Server release 6.10 (Santiago)\n",
+            ),
+        ]);
+        assert_eq!(detection.source, Source::RedHatRelease);
+        let os_release = detection.os_release;
+        assert_eq!(os_release.id.as_deref(), Some("rhel"));
+        assert_eq!(os_release.id_like, ["fedora"]);
+        assert_eq!(os_release.version_id.as_deref(), Some("6.10"));
+        assert_eq!(os_release.version_codename.as_deref(), Some("Santiago"));
+    }
+
+    #[test]
+    fn unreadable_files_are_skipped() {
+        let root = fixture(&[("etc/debian_version", "12.1\n")]);
+        fs::write(root.path().join("etc/lsb-release"), b"DISTRIB_ID=Caf\xe9\n").unwrap();
+        fs::create_dir(root.path().join("etc/centos-release")).unwrap();
+
+        let detection = detect_in(root.path()).unwrap();
+        assert_eq!(detection.source, Source::DebianVersion);
+        assert_eq!(detection.os_release.version_id.as_deref(), Some("12.1"));
+    }
+
+    #[test]
+    fn centos_release() {
+        let line = "CentOS Linux release 7.9.2009 (Core)\n";
+        let detection = detect(&[("etc/centos-release", line), ("etc/redhat-release", line)]);
+        assert_eq!(detection.source, Source::CentOsRelease);
+        let os_release = detection.os_release;
+        assert_eq!(os_release.id.as_deref(), Some("centos"));
+        assert_eq!(os_release.name.as_deref(), Some("CentOS Linux"));
+        assert_eq!(os_release.version_id.as_deref(), Some("7"));
+        assert_eq!(os_release.version.as_deref(), Some("7.9.2009 (Core)"));
+        assert_eq!(os_release.pretty_name.as_deref(), Some(line.trim()));
+        assert!(os_release.is_like("rhel"));
+    }
+
+    #[test]
+    fn suse_release() {
+        let detection = detect(&[(
+            "etc/SuSE-release",
+            "SUSE Linux Enterprise Server 11 (x86_64)\nVERSION = 11\nPATCHLEVEL = 4\n",
+        )]);
+        assert_eq!(detection.source, Source::SuseRelease);
+        let os_release = detection.os_release;
+        assert_eq!(os_release.id.as_deref(), Some("sles"));
+        assert_eq!(os_release.version_id.as_deref(), Some("11.4"));
+        assert_eq!(
+            os_release.pretty_name.as_deref(),
+            Some("SUSE Linux Enterprise Server 11")
+        );
+    }
+
+    #[test]
+    fn single_value_files() {
+        let debian = detect(&[("etc/debian_version", "7.11\n")]).os_release;
+        assert_eq!(debian.id.as_deref(), Some("debian"));
+        assert_eq!(debian.version_id.as_deref(), Some("7.11"));
+
+        let sid = detect(&[("etc/debian_version", "bookworm/sid\n")]).os_release;
+        assert_eq!(sid.version_id, None);
+        assert_eq!(sid.version_codename.as_deref(), Some("bookworm"));
+
+        let alpine = detect(&[("etc/alpine-release", "3.2.3\n")]).os_release;
+        assert_eq!(alpine.id.as_deref(), Some("alpine"));
+        assert_eq!(alpine.version_id.as_deref(), Some("3.2.3"));
+
+        let arch = detect(&[("etc/arch-release", "")]).os_release;
+        assert_eq!(arch.id.as_deref(), Some("arch"));
+        assert_eq!(arch.pretty_name.as_deref(), Some("Arch Linux"));
+
+        let gentoo = detect(&[("etc/gentoo-release", "Gentoo Base System release 2.2\n")]);
+        assert_eq!(gentoo.os_release.id.as_deref(), Some("gentoo"));
+        assert_eq!(gentoo.os_release.version_id.as_deref(), Some("2.2"));
+
+        let slackware = detect(&[("etc/slackware-version", "Slackware 14.2\n")]).os_release;
+        assert_eq!(slackware.id.as_deref(), Some("slackware"));
+        assert_eq!(slackware.version_id.as_deref(), Some("14.2"));
+    }
+
+    #[test]
+    fn nothing_found() {
+        let root = fixture(&[]);
+        let why = detect_in(root.path()).unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::OsRelease;
+use std::fmt;
+
+/// A well-known Linux distribution, as identified by the `ID` of its os-release file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Distro {
+    /// AlmaLinux, `ID=almalinux`.
+    AlmaLinux,
+    /// Alpine Linux, `ID=alpine`.
+    Alpine,
+    /// Amazon Linux, `ID=amzn`.
+    AmazonLinux,
+    /// Arch Linux, `ID=arch`.
+    Arch,
+    /// CentOS Linux, `ID=centos`.
+    CentOs,
+    /// CentOS Stream, `ID=centos` with a `NAME` that contains `Stream`.
+    CentOsStream,
+    /// Debian GNU/Linux, `ID=debian`.
+    Debian,
+    /// elementary OS, `ID=elementary`.
+    ElementaryOs,
+    /// EndeavourOS, `ID=endeavouros`.
+    EndeavourOs,
+    /// Fedora Linux, `ID=fedora`.
+    Fedora,
+    /// Gentoo Linux, `ID=gentoo`.
+    Gentoo,
+    /// Kali Linux, `ID=kali`.
+    Kali,
+    /// Manjaro Linux, `ID=manjaro`.
+    Manjaro,
+    /// Linux Mint, `ID=linuxmint`.
+    Mint,
+    /// NixOS, `ID=nixos`.
+    NixOs,
+    /// openSUSE Leap, `ID=opensuse-leap`.
+    OpenSuseLeap,
+    /// openSUSE Tumbleweed, `ID=opensuse-tumbleweed`.
+    OpenSuseTumbleweed,
+    /// Oracle Linux, `ID=ol`.
+    OracleLinux,
+    /// Pop!_OS, `ID=pop`, or `ID=ubuntu` with a `NAME` of `Pop!_OS`.
+    PopOs,
+    /// Raspbian, `ID=raspbian`.
+    Raspbian,
+    /// Red Hat Enterprise Linux, `ID=rhel`.
+    Rhel,
+    /// Rocky Linux, `ID=rocky`.
+    Rocky,
+    /// SUSE Linux Enterprise Server, `ID=sles`.
+    Sles,
+    /// Ubuntu, `ID=ubuntu`.
+    Ubuntu,
+    /// Void Linux, `ID=void`.
+    Void,
+    /// A distribution that is not known to this crate, with its `ID`.
+    Unknown(String),
+}
+
+impl Distro {
+    /// Map an os-release `ID` to the distribution that uses it.
+    ///
+    /// `centos` is taken to be CentOS Linux; use `OsRelease::distro` to also
+    /// recognize CentOS Stream, which shares the ID.
+    pub fn from_id(id: &str) -> Distro {
+        match id {
+            "almalinux" => Distro::AlmaLinux,
+            "alpine" => Distro::Alpine,
+            "amzn" => Distro::AmazonLinux,
+            "arch" => Distro::Arch,
+            "centos" => Distro::CentOs,
+            "debian" => Distro::Debian,
+            "elementary" => Distro::ElementaryOs,
+            "endeavouros" => Distro::EndeavourOs,
+            "fedora" => Distro::Fedora,
+            "gentoo" => Distro::Gentoo,
+            "kali" => Distro::Kali,
+            "manjaro" => Distro::Manjaro,
+            "linuxmint" => Distro::Mint,
+            "nixos" => Distro::NixOs,
+            "opensuse-leap" => Distro::OpenSuseLeap,
+            "opensuse-tumbleweed" => Distro::OpenSuseTumbleweed,
+            "ol" => Distro::OracleLinux,
+            "pop" => Distro::PopOs,
+            "raspbian" => Distro::Raspbian,
+            "rhel" => Distro::Rhel,
+            "rocky" => Distro::Rocky,
+            "sles" => Distro::Sles,
+            "ubuntu" => Distro::Ubuntu,
+            "void" => Distro::Void,
+            other => Distro::Unknown(other.to_owned()),
+        }
+    }
+
+    /// The os-release `ID` of this distribution.
+    pub fn id(&self) -> &str {
+        match self {
+            Distro::AlmaLinux => "almalinux",
+            Distro::Alpine => "alpine",
+            Distro::AmazonLinux => "amzn",
+            Distro::Arch => "arch",
+            Distro::CentOs | Distro::CentOsStream => "centos",
+            Distro::Debian => "debian",
+            Distro::ElementaryOs => "elementary",
+            Distro::EndeavourOs => "endeavouros",
+            Distro::Fedora => "fedora",
+            Distro::Gentoo => "gentoo",
+            Distro::Kali => "kali",
+            Distro::Manjaro => "manjaro",
+            Distro::Mint => "linuxmint",
+            Distro::NixOs => "nixos",
+            Distro::OpenSuseLeap => "opensuse-leap",
+            Distro::OpenSuseTumbleweed => "opensuse-tumbleweed",
+            Distro::OracleLinux => "ol",
+            Distro::PopOs => "pop",
+            Distro::Raspbian => "raspbian",
+            Distro::Rhel => "rhel",
+            Distro::Rocky => "rocky",
+            Distro::Sles => "sles",
+            Distro::Ubuntu => "ubuntu",
+            Distro::Void => "void",
+            Distro::Unknown(id) => id,
+        }
+    }
+
+    /// The distribution that this one is directly derived from, if any.
+    ///
+    /// **IE:** Pop!_OS is derived from Ubuntu, which is derived from Debian.
+    pub fn parent(&self) -> Option<Distro> {
+        let parent = match self {
+            Distro::Rhel => Distro::Fedora,
+            Distro::AlmaLinux
+            | Distro::CentOs
+            | Distro::CentOsStream
+            | Distro::OracleLinux
+            | Distro::Rocky => Distro::Rhel,
+            Distro::AmazonLinux => Distro::Fedora,
+            Distro::Ubuntu | Distro::Kali | Distro::Raspbian => Distro::Debian,
+            Distro::ElementaryOs | Distro::Mint | Distro::PopOs => Distro::Ubuntu,
+            Distro::EndeavourOs | Distro::Manjaro => Distro::Arch,
+            _ => return None,
+        };
+
+        Some(parent)
+    }
+
+    /// The family of distributions that this one belongs to, if it is known.
+    pub fn family(&self) -> Option<Family> {
+        let family = match self {
+            Distro::AlmaLinux
+            | Distro::AmazonLinux
+            | Distro::CentOs
+            | Distro::CentOsStream
+            | Distro::Fedora
+            | Distro::OracleLinux
+            | Distro::Rhel
+            | Distro::Rocky => Family::RedHat,
+            Distro::Debian
+            | Distro::ElementaryOs
+            | Distro::Kali
+            | Distro::Mint
+            | Distro::PopOs
+            | Distro::Raspbian
+            | Distro::Ubuntu => Family::Debian,
+            Distro::Arch | Distro::EndeavourOs | Distro::Manjaro => Family::Arch,
+            Distro::OpenSuseLeap | Distro::OpenSuseTumbleweed | Distro::Sles => Family::Suse,
+            Distro::Alpine => Family::Alpine,
+            Distro::Gentoo => Family::Gentoo,
+            Distro::NixOs => Family::NixOs,
+            Distro::Void => Family::Void,
+            Distro::Unknown(id) => return Family::from_id(id),
+        };
+
+        Some(family)
+    }
+}
+
+impl fmt::Display for Distro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Distro::AlmaLinux => "AlmaLinux",
+            Distro::Alpine => "Alpine Linux",
+            Distro::AmazonLinux => "Amazon Linux",
+            Distro::Arch => "Arch Linux",
+            Distro::CentOs => "CentOS Linux",
+            Distro::CentOsStream => "CentOS Stream",
+            Distro::Debian => "Debian",
+            Distro::ElementaryOs => "elementary OS",
+            Distro::EndeavourOs => "EndeavourOS",
+            Distro::Fedora => "Fedora Linux",
+            Distro::Gentoo => "Gentoo",
+            Distro::Kali => "Kali Linux",
+            Distro::Manjaro => "Manjaro Linux",
+            Distro::Mint => "Linux Mint",
+            Distro::NixOs => "NixOS",
+            Distro::OpenSuseLeap => "openSUSE Leap",
+            Distro::OpenSuseTumbleweed => "openSUSE Tumbleweed",
+            Distro::OracleLinux => "Oracle Linux",
+            Distro::PopOs => "Pop!_OS",
+            Distro::Raspbian => "Raspbian",
+            Distro::Rhel => "Red Hat Enterprise Linux",
+            Distro::Rocky => "Rocky Linux",
+            Distro::Sles => "SUSE Linux Enterprise Server",
+            Distro::Ubuntu => "Ubuntu",
+            Distro::Void => "Void Linux",
+            Distro::Unknown(id) => id,
+        })
+    }
+}
+
+/// A family of related distributions, named after its common ancestor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Family {
+    /// Alpine Linux, `ID=alpine`.
+    Alpine,
+    /// Arch Linux and its derivatives, such as Manjaro, `ID=arch`.
+    Arch,
+    /// Debian and its derivatives, such as Ubuntu, `ID=debian`.
+    Debian,
+    /// Gentoo Linux, `ID=gentoo`.
+    Gentoo,
+    /// NixOS, `ID=nixos`.
+    NixOs,
+    /// Fedora, Red Hat Enterprise Linux and their rebuilds, `ID=fedora`, `ID=rhel`
+    /// and the IDs of the rebuilds.
+    RedHat,
+    /// SUSE Linux Enterprise and openSUSE, `ID=sles` or `ID=opensuse-*`.
+    Suse,
+    /// Void Linux, `ID=void`.
+    Void,
+}
+
+impl Family {
+    /// The family of the distribution with the os-release `ID` of `id`.
+    ///
+    /// Besides distribution IDs, this recognizes `suse` and `opensuse`, which
+    /// SUSE derivatives list in `ID_LIKE`.
+    pub fn from_id(id: &str) -> Option<Family> {
+        match id {
+            "suse" | "opensuse" => Some(Family::Suse),
+            id => match Distro::from_id(id) {
+                Distro::Unknown(_) => None,
+                distro => distro.family(),
+            },
+        }
+    }
+}
+
+impl OsRelease {
+    /// The distribution that this os-release file describes.
+    ///
+    /// Distributions which reuse the `ID` of another are told apart by `NAME`.
+    pub fn distro(&self) -> Distro {
+        let name = self.name.as_deref().unwrap_or_default();
+        match self.id.as_deref().unwrap_or("linux") {
+            "centos" if name.contains("Stream") => Distro::CentOsStream,
+            // Early Pop!_OS releases identified themselves as Ubuntu.
+            "ubuntu" if name == "Pop!_OS" => Distro::PopOs,
+            id => Distro::from_id(id),
+        }
+    }
+
+    /// The closest known distribution that this one is derived from.
+    ///
+    /// A distribution that reuses the `ID` of one it is derived from, as early
+    /// Pop!_OS did with Ubuntu's, has that as its parent. Otherwise `ID_LIKE` is
+    /// searched, in its given order, so that the file has the final say, and then
+    /// the built-in parent of `distro` is used.
+    pub fn parent_distro(&self) -> Option<Distro> {
+        let distro = self.distro();
+        let own = Distro::from_id(self.id.as_deref().unwrap_or("linux"));
+        let mut ancestors = std::iter::successors(distro.parent(), Distro::parent);
+        if own != distro && ancestors.any(|ancestor| ancestor == own) {
+            return Some(own);
+        }
+
+        self.id_like
+            .iter()
+            .map(|id| Distro::from_id(id))
+            .find(|distro| !matches!(distro, Distro::Unknown(_)))
+            .or_else(|| distro.parent())
+    }
+
+    /// The family of distributions that this one belongs to, checking `ID` before
+    /// each entry of `ID_LIKE`.
+    pub fn family(&self) -> Option<Family> {
+        self.distro()
+            .family()
+            .or_else(|| self.id_like.iter().find_map(|id| Family::from_id(id)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input: &str) -> OsRelease {
+        OsRelease::from_iter(input.lines().map(String::from))
+    }
+
+    #[test]
+    fn known_ids_round_trip() {
+        for id in [
+            "fedora",
+            "rhel",
+            "rocky",
+            "pop",
+            "linuxmint",
+            "opensuse-leap",
+            "amzn",
+        ] {
+            let distro = Distro::from_id(id);
+            assert!(!matches!(distro, Distro::Unknown(_)), "{} is unknown", id);
+            assert_eq!(distro.id(), id);
+        }
+
+        assert_eq!(Distro::from_id("plan9"), Distro::Unknown("plan9".into()));
+    }
+
+    #[test]
+    fn early_pop_os() {
+        let pop = parse("NAME=\"Pop!_OS\"\nID=ubuntu\nID_LIKE=debian");
+        assert_eq!(pop.distro(), Distro::PopOs);
+        assert_eq!(pop.parent_distro(), Some(Distro::Ubuntu));
+        assert_eq!(pop.family(), Some(Family::Debian));
+
+        let pop = parse("NAME=\"Pop!_OS\"\nID=pop\nID_LIKE=\"ubuntu debian\"");
+        assert_eq!(pop.distro(), Distro::PopOs);
+        assert_eq!(pop.parent_distro(), Some(Distro::Ubuntu));
+    }
+
+    #[test]
+    fn centos_stream() {
+        let stream = parse("NAME=\"CentOS Stream\"\nID=centos\nID_LIKE=\"rhel fedora\"");
+        assert_eq!(stream.distro(), Distro::CentOsStream);
+        assert_eq!(stream.parent_distro(), Some(Distro::Rhel));
+        assert_eq!(stream.family(), Some(Family::RedHat));
+        assert_eq!(
+            parse("NAME=\"CentOS Linux\"\nID=centos").distro(),
+            Distro::CentOs
+        );
+    }
+
+    #[test]
+    fn unknown_derivatives() {
+        let derivative = parse("ID=zorin\nID_LIKE=\"ubuntu debian\"");
+        assert_eq!(derivative.distro(), Distro::Unknown("zorin".into()));
+        assert_eq!(derivative.parent_distro(), Some(Distro::Ubuntu));
+        assert_eq!(derivative.family(), Some(Family::Debian));
+
+        let suse = parse("ID=opensuse-microos\nID_LIKE=\"suse opensuse opensuse-tumbleweed\"");
+        assert_eq!(suse.parent_distro(), Some(Distro::OpenSuseTumbleweed));
+        assert_eq!(suse.family(), Some(Family::Suse));
+
+        let unrelated = parse("ID=plan9");
+        assert_eq!(unrelated.parent_distro(), None);
+        assert_eq!(unrelated.family(), None);
+    }
+
+    #[test]
+    fn builtin_parents() {
+        assert_eq!(parse("ID=rocky").parent_distro(), Some(Distro::Rhel));
+        assert_eq!(Distro::Rhel.parent(), Some(Distro::Fedora));
+        assert_eq!(Distro::Fedora.parent(), None);
+    }
+}
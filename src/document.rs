@@ -0,0 +1,304 @@
+use crate::error::ParseErrorKind;
+use crate::parser::{self, Line};
+use crate::OsRelease;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+pub use crate::parser::Quoting;
+
+/// An os-release file that can be edited without disturbing the parts that are not changed.
+///
+/// Comments, blank lines, malformed lines, key order, quoting and line endings are all
+/// kept, so writing out an unmodified document reproduces its input exactly, and
+/// changing a key only rewrites the value of that key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    lines: Vec<DocumentLine>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct DocumentLine {
+    /// The line as written, including its line terminator.
+    text: String,
+    /// The assignment on this line, if it holds a valid one.
+    assignment: Option<Assignment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Assignment {
+    key: String,
+    value: String,
+    /// Byte range of the value as written within the line's `text`.
+    span: Range<usize>,
+}
+
+impl Document {
+    /// Parse the contents of an os-release file.
+    ///
+    /// Lines that are not valid assignments are kept as they are, but do not
+    /// contribute any keys.
+    pub fn parse(text: &str) -> Document {
+        let lines = text
+            .split_inclusive('\n')
+            .map(|text| {
+                let line = text.strip_suffix('\n').unwrap_or(text);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let assignment = match parser::parse_line(line) {
+                    Ok(Line::Assignment { key, value, span }) => Some(Assignment {
+                        key: key.to_owned(),
+                        value: value.into_owned(),
+                        span,
+                    }),
+                    _ => None,
+                };
+
+                DocumentLine {
+                    text: text.to_owned(),
+                    assignment,
+                }
+            })
+            .collect();
+
+        Document { lines }
+    }
+
+    /// Read and parse an os-release file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Document> {
+        Ok(Document::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Every assignment, in the order they appear in the file.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.assignments()
+            .map(|assignment| (assignment.key.as_str(), assignment.value.as_str()))
+    }
+
+    /// The value of `key`.
+    ///
+    /// As with `OsRelease`, the last assignment wins if a key is assigned more than once.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|&(candidate, _)| candidate == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// How the value of `key` is quoted in the file.
+    pub fn quoting(&self, key: &str) -> Option<Quoting> {
+        let index = self.position(key)?;
+        let line = &self.lines[index];
+        let span = line.assignment.as_ref()?.span.clone();
+        Some(Quoting::of(&line.text[span]))
+    }
+
+    /// Set `key` to `value`.
+    ///
+    /// If the key is present, only the value of its last assignment is rewritten,
+    /// keeping its quoting style where that can represent the new value, and any
+    /// comment that follows it. Otherwise a new assignment is appended to the end.
+    /// Values containing line breaks cannot be represented, and are rejected with
+    /// `ParseErrorKind::InvalidValue`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ParseErrorKind> {
+        if !parser::is_valid_key(key) {
+            return Err(ParseErrorKind::InvalidKey(key.to_owned()));
+        } else if !parser::is_valid_value(value) {
+            return Err(ParseErrorKind::InvalidValue(key.to_owned()));
+        }
+
+        if let Some(index) = self.position(key) {
+            let line = &mut self.lines[index];
+            let assignment = line
+                .assignment
+                .as_mut()
+                .expect("position finds assignments");
+            let quoting = Quoting::of(&line.text[assignment.span.clone()]);
+            let written = parser::quote_as(value, quoting);
+
+            line.text.replace_range(assignment.span.clone(), &written);
+            assignment.span.end = assignment.span.start + written.len();
+            assignment.value = value.to_owned();
+            return Ok(());
+        }
+
+        if let Some(last) = self.lines.last_mut() {
+            if !last.text.ends_with('\n') {
+                last.text.push('\n');
+            }
+        }
+
+        let written = parser::quote(value);
+        let start = key.len() + 1;
+        self.lines.push(DocumentLine {
+            text: format!("{}={}\n", key, written),
+            assignment: Some(Assignment {
+                key: key.to_owned(),
+                value: value.to_owned(),
+                span: start..start + written.len(),
+            }),
+        });
+
+        Ok(())
+    }
+
+    /// Remove every assignment of `key`, returning the value that was in effect.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let value = self.get(key).map(String::from);
+        self.lines.retain(|line| {
+            line.assignment
+                .as_ref()
+                .is_none_or(|assignment| assignment.key != key)
+        });
+        value
+    }
+
+    /// The data structure view of this document.
+    pub fn to_os_release(&self) -> OsRelease {
+        OsRelease::from(self)
+    }
+
+    fn assignments(&self) -> impl Iterator<Item = &Assignment> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.assignment.as_ref())
+    }
+
+    /// Index of the line holding the last assignment of `key`.
+    fn position(&self, key: &str) -> Option<usize> {
+        self.lines.iter().rposition(|line| {
+            line.assignment
+                .as_ref()
+                .is_some_and(|assignment| assignment.key == key)
+        })
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.lines
+            .iter()
+            .try_for_each(|line| f.write_str(&line.text))
+    }
+}
+
+impl From<&Document> for OsRelease {
+    fn from(document: &Document) -> Self {
+        let mut os_release = OsRelease::default();
+        for (key, value) in document.entries() {
+            os_release.set(key, value.to_owned());
+        }
+        os_release
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &str = "# Managed by the image build\n\
+        NAME='Example OS'\n\
+        ID=example\n\
+        \n\
+        IMAGE_VERSION=\"1.2\"   # bumped by CI\n\
+        not an assignment\n\
+        VERSION_ID=1\n";
+
+    #[test]
+    fn lossless() {
+        let fedora = include_str!("../fedora-rawhide-os-release");
+        for input in [INPUT, fedora, "ID=a\r\nNAME=b", "", "\n\n"] {
+            assert_eq!(Document::parse(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn get_and_quoting() {
+        let document = Document::parse(INPUT);
+        assert_eq!(document.get("NAME"), Some("Example OS"));
+        assert_eq!(document.quoting("NAME"), Some(Quoting::Single));
+        assert_eq!(document.get("IMAGE_VERSION"), Some("1.2"));
+        assert_eq!(document.quoting("IMAGE_VERSION"), Some(Quoting::Double));
+        assert_eq!(document.quoting("ID"), Some(Quoting::Bare));
+        assert_eq!(document.get("LOGO"), None);
+        assert_eq!(
+            document.entries().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["NAME", "ID", "IMAGE_VERSION", "VERSION_ID"]
+        );
+    }
+
+    #[test]
+    fn set_rewrites_only_the_value() {
+        let mut document = Document::parse(INPUT);
+        document.set("IMAGE_VERSION", "1.3").unwrap();
+        document.set("NAME", "Example OS 2").unwrap();
+        document.set("ID", "example two").unwrap();
+
+        assert_eq!(
+            document.to_string(),
+            INPUT
+                .replace("\"1.2\"", "\"1.3\"")
+                .replace("'Example OS'", "'Example OS 2'")
+                .replace("ID=example", "ID=\"example two\"")
+        );
+        assert_eq!(document.get("IMAGE_VERSION"), Some("1.3"));
+        assert_eq!(document.to_os_release().id.as_deref(), Some("example two"));
+    }
+
+    #[test]
+    fn set_appends_missing_keys() {
+        let mut document = Document::parse("ID=example");
+        document.set("VARIANT", "Server Edition").unwrap();
+        assert_eq!(
+            document.to_string(),
+            "ID=example\nVARIANT=\"Server Edition\"\n"
+        );
+        document.set("VARIANT", "Cloud").unwrap();
+        assert_eq!(document.to_string(), "ID=example\nVARIANT=\"Cloud\"\n");
+
+        assert_eq!(
+            document.set("BAD-KEY", "x"),
+            Err(ParseErrorKind::InvalidKey("BAD-KEY".into()))
+        );
+    }
+
+    #[test]
+    fn set_rejects_line_breaks() {
+        let mut document = Document::parse(INPUT);
+        for value in ["a\"\nID=evil\n", "a\rb"] {
+            assert_eq!(
+                document.set("NAME", value),
+                Err(ParseErrorKind::InvalidValue("NAME".into()))
+            );
+            assert_eq!(
+                document.set("NEW", value),
+                Err(ParseErrorKind::InvalidValue("NEW".into()))
+            );
+        }
+        assert_eq!(document.to_string(), INPUT);
+    }
+
+    #[test]
+    fn set_updates_last_assignment() {
+        let mut document = Document::parse("ID=a\nID=b\n");
+        document.set("ID", "c").unwrap();
+        assert_eq!(document.to_string(), "ID=a\nID=c\n");
+        assert_eq!(document.remove("ID"), Some("c".into()));
+        assert_eq!(document.to_string(), "");
+        assert_eq!(document.remove("ID"), None);
+    }
+
+    #[test]
+    fn matches_os_release() {
+        let fedora = include_str!("../fedora-rawhide-os-release");
+        let expected = OsRelease::from_iter(fedora.lines().map(String::from));
+        assert_eq!(Document::parse(fedora).to_os_release(), expected);
+        assert_eq!(
+            Document::parse(INPUT)
+                .to_os_release()
+                .image_version
+                .as_deref(),
+            Some("1.2")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io;
+
+/// An error encountered while reading os-release data.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be opened or read.
+    Io(io::Error),
+    /// The file was read, but one or more of its lines are malformed.
+    Parse(Vec<ParseError>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(why) => write!(f, "failed to read os-release: {}", why),
+            Error::Parse(errors) => {
+                f.write_str("malformed os-release")?;
+                for (i, error) in errors.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(why) => Some(why),
+            Error::Parse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(why: io::Error) -> Self {
+        Error::Io(why)
+    }
+}
+
+/// Reports parse errors as `io::ErrorKind::InvalidData`.
+impl From<Error> for io::Error {
+    fn from(why: Error) -> Self {
+        match why {
+            Error::Io(why) => why,
+            why => io::Error::new(io::ErrorKind::InvalidData, why),
+        }
+    }
+}
+
+/// A problem found on a single line of an os-release file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line on which the problem was found, starting from 1.
+    pub line: usize,
+    /// What is wrong with the line.
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The reason that a line of an os-release file was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The line is neither blank, a comment, nor a `KEY=value` assignment.
+    MissingAssignment,
+    /// The key is not a valid shell variable name.
+    InvalidKey(String),
+    /// A quoted value is missing its closing quote.
+    UnterminatedQuote,
+    /// The key was already assigned on an earlier line.
+    DuplicateKey {
+        /// The repeated key.
+        key: String,
+        /// The line of the first assignment.
+        first: usize,
+    },
+    /// The value of the key contains a line break, which cannot be written on one line.
+    InvalidValue(String),
+    /// The line is not valid UTF-8.
+    InvalidUtf8 {
+        /// Byte offset within the line of the first invalid byte.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::MissingAssignment => f.write_str("expected a KEY=value assignment"),
+            ParseErrorKind::InvalidKey(key) => write!(f, "invalid key `{}`", key),
+            ParseErrorKind::UnterminatedQuote => f.write_str("unterminated quote"),
+            ParseErrorKind::DuplicateKey { key, first } => {
+                write!(
+                    f,
+                    "duplicate key `{}`, first assigned on line {}",
+                    key, first
+                )
+            }
+            ParseErrorKind::InvalidValue(key) => {
+                write!(f, "the value of `{}` contains a line break", key)
+            }
+            ParseErrorKind::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 at byte {}", offset)
+            }
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Extension images, as merged by `systemd-sysext` and `systemd-confext`.
+//!
+//! An extension image describes the OS it was built for in an extension-release file,
+//! which uses the os-release format. It is only merged into a host that it matches,
+//! which `ExtensionHost::check` decides with the rules that systemd applies.
+
+use crate::OsRelease;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The kinds of extension image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExtensionClass {
+    /// A system extension, which extends `/usr` and `/opt`.
+    Sysext,
+    /// A configuration extension, which extends `/etc`.
+    Confext,
+}
+
+impl ExtensionClass {
+    /// The directory within the image that holds its extension-release file.
+    ///
+    /// **IE:** `/usr/lib/extension-release.d`
+    pub fn release_dir(self) -> &'static str {
+        match self {
+            ExtensionClass::Sysext => "/usr/lib/extension-release.d",
+            ExtensionClass::Confext => "/etc/extension-release.d",
+        }
+    }
+
+    /// The key that carries the API level that images of this class are built for.
+    ///
+    /// **IE:** `SYSEXT_LEVEL`
+    pub fn level_key(self) -> &'static str {
+        match self {
+            ExtensionClass::Sysext => "SYSEXT_LEVEL",
+            ExtensionClass::Confext => "CONFEXT_LEVEL",
+        }
+    }
+
+    /// The key that lists the scopes an image of this class may be used in.
+    ///
+    /// **IE:** `SYSEXT_SCOPE`
+    pub fn scope_key(self) -> &'static str {
+        match self {
+            ExtensionClass::Sysext => "SYSEXT_SCOPE",
+            ExtensionClass::Confext => "CONFEXT_SCOPE",
+        }
+    }
+
+    fn level(self, os_release: &OsRelease) -> Option<&str> {
+        match self {
+            ExtensionClass::Sysext => os_release.sysext_level.as_deref(),
+            ExtensionClass::Confext => os_release.confext_level.as_deref(),
+        }
+        .filter(|level| !level.is_empty())
+    }
+
+    fn scope(self, os_release: &OsRelease) -> Option<&str> {
+        match self {
+            ExtensionClass::Sysext => os_release.sysext_scope.as_deref(),
+            ExtensionClass::Confext => os_release.confext_scope.as_deref(),
+        }
+    }
+}
+
+/// Where an extension image is being used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// The booted system.
+    System,
+    /// The initrd.
+    Initrd,
+    /// A portable service.
+    Portable,
+}
+
+impl Scope {
+    /// The name of this scope, as listed in `SYSEXT_SCOPE` and `CONFEXT_SCOPE`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::System => "system",
+            Scope::Initrd => "initrd",
+            Scope::Portable => "portable",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl OsRelease {
+    /// Attempt to parse the extension-release file of the extension image `name`,
+    /// whose root directory is `root`.
+    ///
+    /// The file is `extension-release.<name>`, in the `release_dir` of its class.
+    pub fn extension_release_in<P: AsRef<Path>>(
+        root: P,
+        class: ExtensionClass,
+        name: &str,
+    ) -> io::Result<OsRelease> {
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{}` is not a valid extension image name", name),
+            ));
+        }
+
+        let path = extension_release_path(class, name);
+        OsRelease::from_file_in(root, path)
+    }
+}
+
+/// The path of the extension-release file of the extension image `name`, relative to
+/// the root of the image.
+///
+/// **IE:** `/usr/lib/extension-release.d/extension-release.debug-tools`
+pub fn extension_release_path(class: ExtensionClass, name: &str) -> PathBuf {
+    Path::new(class.release_dir()).join(format!("extension-release.{}", name))
+}
+
+/// A system that extension images are checked against before they are merged into it.
+#[derive(Clone, Debug)]
+pub struct ExtensionHost<'a> {
+    os_release: &'a OsRelease,
+    scope: Scope,
+    architecture: &'a str,
+}
+
+impl<'a> ExtensionHost<'a> {
+    /// The system described by `os_release`, in the `system` scope, running on the
+    /// architecture that this program was built for.
+    pub fn new(os_release: &'a OsRelease) -> Self {
+        ExtensionHost {
+            os_release,
+            scope: Scope::System,
+            architecture: native_architecture(),
+        }
+    }
+
+    /// Check images for use in `scope` instead.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Check images for a host running on `architecture` instead, named as in
+    /// `ARCHITECTURE`, such as `arm64`.
+    pub fn architecture(mut self, architecture: &'a str) -> Self {
+        self.architecture = architecture;
+        self
+    }
+
+    /// Whether the extension image of `class`, described by `extension`, may be merged
+    /// into this host, and if not, why.
+    ///
+    /// This follows systemd: the image must be meant for the scope, built for the
+    /// host's architecture unless its `ARCHITECTURE` is `_any`, and built for the
+    /// host's `ID` unless that is `_any`. The API level must then match if both the
+    /// host and the image declare one, and otherwise `VERSION_ID` must. A host that
+    /// declares neither, as rolling releases do, accepts any image with its `ID`.
+    pub fn check(&self, class: ExtensionClass, extension: &OsRelease) -> Result<(), Incompatible> {
+        // Images that do not list their scopes are meant for `system` and `portable`.
+        let scopes = class.scope(extension).unwrap_or("system portable");
+        if !scopes
+            .split_whitespace()
+            .any(|scope| scope == self.scope.as_str())
+        {
+            return Err(Incompatible::Scope(self.scope));
+        }
+
+        match extension.architecture.as_deref() {
+            None | Some("" | "_any") => (),
+            Some(architecture) if architecture == self.architecture => (),
+            Some(architecture) => return Err(Incompatible::Architecture(architecture.into())),
+        }
+
+        let host = self.os_release;
+        match extension.id.as_deref() {
+            None | Some("") => return Err(Incompatible::MissingId),
+            Some("_any") => return Ok(()),
+            Some(id) if Some(id) == host.id.as_deref() => (),
+            Some(id) => return Err(Incompatible::Id(id.into())),
+        }
+
+        let host_version = host
+            .version_id
+            .as_deref()
+            .filter(|version| !version.is_empty());
+        match (class.level(host), class.level(extension), host_version) {
+            (Some(host), Some(extension), _) if host != extension => Err(Incompatible::Level {
+                host: host.into(),
+                extension: extension.into(),
+            }),
+            (Some(_), Some(_), _) => Ok(()),
+            (_, _, Some(host)) => match extension.version_id.as_deref() {
+                None | Some("") => Err(Incompatible::MissingVersionId),
+                Some(extension) if extension != host => Err(Incompatible::VersionId {
+                    host: host.into(),
+                    extension: extension.into(),
+                }),
+                Some(_) => Ok(()),
+            },
+            // Rolling releases, or a host with a level that the image lacks and no
+            // version to compare instead.
+            (_, _, None) => Ok(()),
+        }
+    }
+}
+
+/// The reason that an extension image does not match a host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Incompatible {
+    /// The image is not meant for the scope it is being used in.
+    Scope(Scope),
+    /// The image is built for a different architecture.
+    Architecture(String),
+    /// The image does not say which OS it is built for.
+    MissingId,
+    /// The image is built for a different OS.
+    Id(String),
+    /// The image is built for a different API level.
+    Level { host: String, extension: String },
+    /// The image does not say which version of the OS it is built for.
+    MissingVersionId,
+    /// The image is built for a different version of the OS.
+    VersionId { host: String, extension: String },
+}
+
+impl fmt::Display for Incompatible {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Incompatible::Scope(scope) => {
+                write!(f, "extension is not meant for the {} scope", scope)
+            }
+            Incompatible::Architecture(architecture) => {
+                write!(
+                    f,
+                    "extension is built for the {} architecture",
+                    architecture
+                )
+            }
+            Incompatible::MissingId => f.write_str("extension does not set ID"),
+            Incompatible::Id(id) => write!(f, "extension is built for ID `{}`", id),
+            Incompatible::Level { host, extension } => write!(
+                f,
+                "extension is built for API level `{}`, but the host is at `{}`",
+                extension, host
+            ),
+            Incompatible::MissingVersionId => f.write_str("extension does not set VERSION_ID"),
+            Incompatible::VersionId { host, extension } => write!(
+                f,
+                "extension is built for VERSION_ID `{}`, but the host is at `{}`",
+                extension, host
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Incompatible {}
+
+/// The architecture of this program, named as in `ARCHITECTURE`.
+fn native_architecture() -> &'static str {
+    architecture(std::env::consts::ARCH, cfg!(target_endian = "little"))
+}
+
+/// The name that systemd's architecture table gives to the Rust architecture `arch`
+/// when it runs with the given byte order.
+///
+/// Architectures that systemd names as Rust does, such as `riscv64`, `s390x` and
+/// `sparc64`, are passed through, as are those missing from its table.
+fn architecture(arch: &'static str, little_endian: bool) -> &'static str {
+    match (arch, little_endian) {
+        ("x86", _) => "x86",
+        ("x86_64", _) => "x86-64",
+        ("aarch64", true) => "arm64",
+        ("aarch64", false) => "arm64-be",
+        ("arm", true) => "arm",
+        ("arm", false) => "arm-be",
+        ("powerpc", true) => "ppc-le",
+        ("powerpc", false) => "ppc",
+        ("powerpc64", true) => "ppc64-le",
+        ("powerpc64", false) => "ppc64",
+        ("mips" | "mips32r6", true) => "mips-le",
+        ("mips" | "mips32r6", false) => "mips",
+        ("mips64" | "mips64r6", true) => "mips64-le",
+        ("mips64" | "mips64r6", false) => "mips64",
+        (other, _) => other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn os_release(text: &str) -> OsRelease {
+        text.parse().unwrap()
+    }
+
+    fn check(host: &str, extension: &str) -> Result<(), Incompatible> {
+        let host = os_release(host);
+        let extension = os_release(extension);
+        ExtensionHost::new(&host)
+            .architecture("x86-64")
+            .check(ExtensionClass::Sysext, &extension)
+    }
+
+    const HOST: &str = "ID=fedora\nVERSION_ID=38\n";
+
+    #[test]
+    fn version_id() {
+        assert_eq!(check(HOST, "ID=fedora\nVERSION_ID=38\n"), Ok(()));
+        assert_eq!(
+            check(HOST, "ID=fedora\nVERSION_ID=37\n"),
+            Err(Incompatible::VersionId {
+                host: "38".into(),
+                extension: "37".into()
+            })
+        );
+        assert_eq!(
+            check(HOST, "ID=fedora\n"),
+            Err(Incompatible::MissingVersionId)
+        );
+        assert_eq!(
+            check(HOST, "ID=debian\nVERSION_ID=38\n"),
+            Err(Incompatible::Id("debian".into()))
+        );
+        assert_eq!(check(HOST, "VERSION_ID=38\n"), Err(Incompatible::MissingId));
+    }
+
+    #[test]
+    fn any_id() {
+        assert_eq!(check(HOST, "ID=_any\n"), Ok(()));
+        assert_eq!(
+            check(HOST, "ID=_any\nARCHITECTURE=arm64\n"),
+            Err(Incompatible::Architecture("arm64".into()))
+        );
+        assert_eq!(check(HOST, "ID=_any\nARCHITECTURE=_any\n"), Ok(()));
+    }
+
+    #[test]
+    fn api_level() {
+        let host = "ID=fedora\nVERSION_ID=38\nSYSEXT_LEVEL=1.2\n";
+        // A matching level takes the place of VERSION_ID.
+        assert_eq!(check(host, "ID=fedora\nSYSEXT_LEVEL=1.2\n"), Ok(()));
+        assert_eq!(
+            check(host, "ID=fedora\nVERSION_ID=38\nSYSEXT_LEVEL=1.3\n"),
+            Err(Incompatible::Level {
+                host: "1.2".into(),
+                extension: "1.3".into()
+            })
+        );
+        // Without a level on the image, VERSION_ID is compared.
+        assert_eq!(check(host, "ID=fedora\nVERSION_ID=38\n"), Ok(()));
+        assert_eq!(
+            check(host, "ID=fedora\nVERSION_ID=39\n"),
+            Err(Incompatible::VersionId {
+                host: "38".into(),
+                extension: "39".into()
+            })
+        );
+
+        // A confext is checked against CONFEXT_LEVEL instead.
+        let host = os_release(host);
+        let extension = os_release("ID=fedora\nSYSEXT_LEVEL=1.2\n");
+        assert_eq!(
+            ExtensionHost::new(&host).check(ExtensionClass::Confext, &extension),
+            Err(Incompatible::MissingVersionId)
+        );
+    }
+
+    #[test]
+    fn rolling_host() {
+        let arch = "ID=arch\n";
+        assert_eq!(check(arch, "ID=arch\n"), Ok(()));
+        assert_eq!(check(arch, "ID=arch\nVERSION_ID=2023\n"), Ok(()));
+        assert_eq!(
+            check(arch, "ID=fedora\n"),
+            Err(Incompatible::Id("fedora".into()))
+        );
+    }
+
+    #[test]
+    fn scope() {
+        let host = os_release(HOST);
+        let initrd = ExtensionHost::new(&host).scope(Scope::Initrd);
+        let image = os_release("ID=_any\n");
+        assert_eq!(
+            initrd.check(ExtensionClass::Sysext, &image),
+            Err(Incompatible::Scope(Scope::Initrd))
+        );
+
+        let image = os_release("ID=_any\nSYSEXT_SCOPE=\"initrd system\"\n");
+        assert_eq!(initrd.check(ExtensionClass::Sysext, &image), Ok(()));
+        assert_eq!(
+            initrd.check(ExtensionClass::Confext, &image),
+            Err(Incompatible::Scope(Scope::Initrd))
+        );
+    }
+
+    #[test]
+    fn extension_release_in() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root
+            .path()
+            .join("usr/lib/extension-release.d/extension-release.tools");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "ID=fedora\nSYSEXT_LEVEL=1\n").unwrap();
+
+        let extension =
+            OsRelease::extension_release_in(root.path(), ExtensionClass::Sysext, "tools").unwrap();
+        assert_eq!(extension.sysext_level.as_deref(), Some("1"));
+
+        let missing =
+            OsRelease::extension_release_in(root.path(), ExtensionClass::Confext, "tools");
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+        let invalid = OsRelease::extension_release_in(root.path(), ExtensionClass::Sysext, "../x");
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn architecture_names() {
+        assert_eq!(architecture("x86", true), "x86");
+        assert_eq!(architecture("x86_64", true), "x86-64");
+        assert_eq!(architecture("aarch64", true), "arm64");
+        assert_eq!(architecture("aarch64", false), "arm64-be");
+        assert_eq!(architecture("arm", false), "arm-be");
+        assert_eq!(architecture("powerpc", false), "ppc");
+        assert_eq!(architecture("powerpc", true), "ppc-le");
+        assert_eq!(architecture("powerpc64", false), "ppc64");
+        assert_eq!(architecture("powerpc64", true), "ppc64-le");
+        assert_eq!(architecture("mips", false), "mips");
+        assert_eq!(architecture("mips", true), "mips-le");
+        assert_eq!(architecture("mips64", false), "mips64");
+        assert_eq!(architecture("mips64r6", true), "mips64-le");
+        assert_eq!(architecture("riscv64", true), "riscv64");
+        assert_eq!(architecture("s390x", false), "s390x");
+        assert_eq!(architecture("loongarch64", true), "loongarch64");
+    }
+}
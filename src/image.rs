@@ -0,0 +1,673 @@
+//! Identification of the distribution that a container image is built from, without
+//! unpacking the image or needing a container runtime.
+//!
+//! OCI image layouts, either as directories or as tarballs, the tarballs written by
+//! `docker save`, and tarballs of a root filesystem are supported. Layers are applied
+//! in order, honouring their whiteouts, and symlinks such as
+//! `/etc/os-release -> ../usr/lib/os-release` are resolved within the image.
+
+use crate::os_release::{self, OS_RELEASE_PATHS};
+use crate::root::MAX_SYMLINKS;
+use crate::OsRelease;
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tar::EntryType;
+
+/// How deeply OCI image indexes may nest.
+const MAX_INDEX_DEPTH: usize = 8;
+
+/// The kinds of image that can be inspected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// An OCI image layout, with an `index.json` and content-addressed blobs.
+    OciLayout,
+    /// The format written by `docker save`, with a `manifest.json` listing the layers.
+    DockerArchive,
+    /// A tarball of a root filesystem, treated as a single layer.
+    Rootfs,
+}
+
+impl Format {
+    /// Determine the format of the image at `path`.
+    ///
+    /// Directories must be OCI image layouts or unpacked `docker save` archives;
+    /// tarballs that are neither are taken to hold a root filesystem.
+    pub fn detect<P: AsRef<Path>>(path: P) -> io::Result<Format> {
+        let store = Store::new(path.as_ref())?;
+        match store.contains(["manifest.json", "oci-layout"])? {
+            [true, _] => Ok(Format::DockerArchive),
+            [false, true] => Ok(Format::OciLayout),
+            [false, false] if matches!(store, Store::Archive(_)) => Ok(Format::Rootfs),
+            [false, false] => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a container image", path.as_ref().display()),
+            )),
+        }
+    }
+}
+
+/// The os-release data of the image at `path`, detecting its format.
+pub fn inspect<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
+    let format = Format::detect(&path)?;
+    inspect_as(path, format)
+}
+
+/// The os-release data of the image at `path`, which is in the given `format`.
+///
+/// Of an image that is built for several platforms, the first platform listed is
+/// inspected.
+pub fn inspect_as<P: AsRef<Path>>(path: P, format: Format) -> io::Result<OsRelease> {
+    let path = path.as_ref();
+    let (store, layers) = match format {
+        Format::OciLayout => {
+            let store = Store::new(path)?;
+            let layers = oci_layers(&store)?;
+            (store, layers)
+        }
+        Format::DockerArchive => {
+            let store = Store::new(path)?;
+            let layers = docker_layers(&store)?;
+            (store, layers)
+        }
+        Format::Rootfs => {
+            let name = path.file_name().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a file", path.display()),
+                )
+            })?;
+            let dir = path.parent().unwrap_or(Path::new("."));
+            (
+                Store::Dir(dir.to_path_buf()),
+                vec![name.to_string_lossy().into_owned()],
+            )
+        }
+    };
+
+    let mut tree = Tree::default();
+    for (index, layer) in layers.iter().enumerate() {
+        store.with(layer, |reader| tree.apply(index, reader))?;
+    }
+
+    for path in OS_RELEASE_PATHS {
+        match tree.resolve(path)? {
+            Some((layer, entry)) => {
+                return store.with(&layers[layer], |reader| read_entry(reader, entry))
+            }
+            None => continue,
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "the image does not contain an os-release file",
+    ))
+}
+
+/// The layers of an OCI image layout, from the bottom up.
+fn oci_layers(store: &Store) -> io::Result<Vec<String>> {
+    let mut document: Value = store.with("index.json", read_json)?;
+    for _ in 0..MAX_INDEX_DEPTH {
+        if let Some(layers) = document["layers"].as_array() {
+            return layers.iter().map(blob_path).collect();
+        }
+
+        // Skip the attestations that builders list as the `unknown` platform.
+        let descriptor = document["manifests"]
+            .as_array()
+            .and_then(|manifests| {
+                manifests
+                    .iter()
+                    .find(|manifest| manifest["platform"]["os"] != "unknown")
+            })
+            .ok_or_else(|| invalid("image index lists no manifests".into()))?;
+        document = store.with(&blob_path(descriptor)?, read_json)?;
+    }
+
+    Err(invalid("image indexes are nested too deeply".into()))
+}
+
+/// The layers of a `docker save` archive, from the bottom up.
+fn docker_layers(store: &Store) -> io::Result<Vec<String>> {
+    let manifest: Value = store.with("manifest.json", read_json)?;
+    manifest[0]["Layers"]
+        .as_array()
+        .ok_or_else(|| invalid("manifest.json lists no layers".into()))?
+        .iter()
+        .map(|layer| {
+            layer
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| invalid(format!("invalid layer in manifest.json: {}", layer)))
+        })
+        .collect()
+}
+
+/// The path of the blob that an OCI descriptor refers to.
+fn blob_path(descriptor: &Value) -> io::Result<String> {
+    let digest = descriptor["digest"].as_str().unwrap_or_default();
+    let is_algorithm = |c: char| c.is_ascii_alphanumeric() || "+._-".contains(c);
+    let is_encoded = |c: char| c.is_ascii_alphanumeric() || "=_-".contains(c);
+    match digest.split_once(':') {
+        Some((algorithm, encoded))
+            if !algorithm.is_empty()
+                && !encoded.is_empty()
+                && algorithm.chars().all(is_algorithm)
+                && encoded.chars().all(is_encoded) =>
+        {
+            Ok(format!("blobs/{}/{}", algorithm, encoded))
+        }
+        _ => Err(invalid(format!("invalid digest `{}`", digest))),
+    }
+}
+
+fn read_json(reader: &mut dyn Read) -> io::Result<Value> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Parse the `entry`th entry of the layer in `reader`.
+fn read_entry(reader: &mut dyn Read, entry: usize) -> io::Result<OsRelease> {
+    let mut archive = tar::Archive::new(decompress(reader)?);
+    let entry = archive
+        .entries()?
+        .nth(entry)
+        .ok_or_else(|| invalid("layer changed while it was being read".into()))??;
+    os_release::read(entry)
+}
+
+/// Undo the compression of a layer or archive, if it is compressed.
+fn decompress<'a, R: Read + 'a>(reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(GzDecoder::new(reader)))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "zstd-compressed layers are not supported",
+        ))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// `path` relative to the root, with `.` and `..` resolved lexically and no leading or
+/// trailing slashes. `..` cannot climb above the root.
+fn clean(path: &str) -> String {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Where the files of an image are kept.
+enum Store {
+    /// An unpacked directory.
+    Dir(PathBuf),
+    /// A tarball, which may be compressed.
+    Archive(PathBuf),
+}
+
+impl Store {
+    fn new(path: &Path) -> io::Result<Store> {
+        Ok(match fs::metadata(path)?.is_dir() {
+            true => Store::Dir(path.to_path_buf()),
+            false => Store::Archive(path.to_path_buf()),
+        })
+    }
+
+    /// Which of `names` are present.
+    fn contains<const N: usize>(&self, names: [&str; N]) -> io::Result<[bool; N]> {
+        let mut found = [false; N];
+        match self {
+            Store::Dir(dir) => {
+                for (found, name) in found.iter_mut().zip(names) {
+                    *found = dir.join(name).exists();
+                }
+            }
+            Store::Archive(path) => {
+                let mut archive = tar::Archive::new(decompress(File::open(path)?)?);
+                for entry in archive.entries()? {
+                    let path = clean(&entry?.path()?.to_string_lossy());
+                    if let Some(index) = names.iter().position(|&name| name == path) {
+                        found[index] = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Call `f` with the contents of the file `name`.
+    ///
+    /// Within archives, symlinks and hard links to the file are followed, as
+    /// `docker save` links layers that are shared between images.
+    fn with<T>(&self, name: &str, f: impl FnOnce(&mut dyn Read) -> io::Result<T>) -> io::Result<T> {
+        let mut name = clean(name);
+        let path = match self {
+            Store::Dir(dir) => return f(&mut File::open(dir.join(name))?),
+            Store::Archive(path) => path,
+        };
+
+        for _ in 0..MAX_SYMLINKS {
+            let mut archive = tar::Archive::new(decompress(File::open(path)?)?);
+            let mut target = None;
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if clean(&entry.path()?.to_string_lossy()) != name {
+                    continue;
+                }
+
+                let link = entry
+                    .link_name()?
+                    .map(|link| link.to_string_lossy().into_owned());
+                target = match (entry.header().entry_type(), link) {
+                    (EntryType::Symlink, Some(link)) => {
+                        let dir = name.rsplit_once('/').map_or("", |(dir, _)| dir);
+                        Some(clean(&format!("{}/{}", dir, link)))
+                    }
+                    (EntryType::Link, Some(link)) => Some(clean(&link)),
+                    _ => return f(&mut entry),
+                };
+                break;
+            }
+
+            name = target.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} does not contain {}", path.display(), name),
+                )
+            })?;
+        }
+
+        Err(too_many_links(&name))
+    }
+}
+
+fn too_many_links(path: &str) -> io::Error {
+    invalid(format!("too many levels of symbolic links at /{}", path))
+}
+
+/// What a path in the image refers to.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Dir,
+    /// A regular file, held by the `entry`th entry of the layer at index `layer`.
+    File {
+        layer: usize,
+        entry: usize,
+    },
+    Symlink(String),
+    /// Anything else, such as a device node or a FIFO.
+    Other,
+}
+
+/// The file tree of an image, as built up by applying its layers in order.
+#[derive(Default)]
+struct Tree {
+    nodes: BTreeMap<String, Node>,
+}
+
+impl Tree {
+    /// Apply the layer at index `layer`, read from `reader`, on top of the tree.
+    fn apply(&mut self, layer: usize, reader: &mut dyn Read) -> io::Result<()> {
+        let mut archive = tar::Archive::new(decompress(reader)?);
+        let mut additions = Vec::new();
+        for (index, entry) in archive.entries()?.enumerate() {
+            let entry = entry?;
+            let path = clean(&entry.path()?.to_string_lossy());
+            let (dir, name) = path.rsplit_once('/').unwrap_or(("", &path));
+
+            // Whiteouts only hide what the layers below hold, so they are applied
+            // before anything this layer adds.
+            if name == ".wh..wh..opq" {
+                self.remove_children(dir);
+                continue;
+            } else if let Some(hidden) = name.strip_prefix(".wh.") {
+                let hidden = match dir {
+                    "" => hidden.to_owned(),
+                    dir => format!("{}/{}", dir, hidden),
+                };
+                self.remove_children(&hidden);
+                self.nodes.remove(&hidden);
+                continue;
+            }
+
+            let link = entry
+                .link_name()?
+                .map(|link| link.to_string_lossy().into_owned());
+            let node = match (entry.header().entry_type(), link) {
+                (EntryType::Directory, _) => Some(Node::Dir),
+                (EntryType::Symlink, Some(target)) => Some(Node::Symlink(target)),
+                // Resolved once the entries before it have been added.
+                (EntryType::Link, Some(target)) => {
+                    additions.push((path, Err(clean(&target))));
+                    continue;
+                }
+                (EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse, _) => {
+                    Some(Node::File {
+                        layer,
+                        entry: index,
+                    })
+                }
+                _ => Some(Node::Other),
+            };
+            additions.extend(node.map(|node| (path, Ok(node))));
+        }
+
+        for (path, node) in additions {
+            let node = match node {
+                Ok(node) => node,
+                Err(target) => match self.nodes.get(&target) {
+                    Some(node) => node.clone(),
+                    None => continue,
+                },
+            };
+
+            // Anything but a directory hides whatever lower layers held beneath it.
+            if node != Node::Dir || self.nodes.get(&path).is_some_and(|old| *old != Node::Dir) {
+                self.remove_children(&path);
+            }
+            self.nodes.insert(path, node);
+        }
+
+        Ok(())
+    }
+
+    /// Remove everything beneath the directory `dir`.
+    fn remove_children(&mut self, dir: &str) {
+        if dir.is_empty() {
+            self.nodes.clear();
+            return;
+        }
+
+        let prefix = format!("{}/", dir);
+        let children: Vec<String> = self
+            .nodes
+            .range(prefix.clone()..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for child in children {
+            self.nodes.remove(&child);
+        }
+    }
+
+    /// The file that `path` refers to, following symlinks as if the image were the
+    /// root directory, so that neither absolute links nor `..` can leave it.
+    fn resolve(&self, path: &str) -> io::Result<Option<(usize, usize)>> {
+        let mut resolved: Vec<String> = Vec::new();
+        let mut pending: Vec<String> = path.split('/').rev().map(String::from).collect();
+        let mut links = 0;
+
+        while let Some(part) = pending.pop() {
+            match part.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    resolved.pop();
+                    continue;
+                }
+                _ => resolved.push(part),
+            }
+
+            if let Some(Node::Symlink(target)) = self.nodes.get(&resolved.join("/")) {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(too_many_links(&clean(path)));
+                }
+
+                resolved.pop();
+                if target.starts_with('/') {
+                    resolved.clear();
+                }
+                pending.extend(target.split('/').rev().map(String::from));
+            }
+        }
+
+        Ok(match self.nodes.get(&resolved.join("/")) {
+            Some(&Node::File { layer, entry }) => Some((layer, entry)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    enum Entry {
+        File(&'static str),
+        Symlink(&'static str),
+        Hardlink(&'static str),
+        Dir,
+    }
+
+    fn tar(entries: &[(&str, Entry)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, entry) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            header.set_size(0);
+            let contents = match entry {
+                Entry::File(contents) => {
+                    header.set_entry_type(EntryType::Regular);
+                    header.set_size(contents.len() as u64);
+                    contents.as_bytes()
+                }
+                Entry::Symlink(target) | Entry::Hardlink(target) => {
+                    header.set_entry_type(match entry {
+                        Entry::Symlink(_) => EntryType::Symlink,
+                        _ => EntryType::Link,
+                    });
+                    header.set_link_name(target).unwrap();
+                    &[]
+                }
+                Entry::Dir => {
+                    header.set_entry_type(EntryType::Directory);
+                    &[]
+                }
+            };
+            builder.append_data(&mut header, path, contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn id(path: &Path) -> Option<String> {
+        inspect(path).unwrap().id
+    }
+
+    #[test]
+    fn rootfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rootfs.tar.gz");
+        let rootfs = tar(&[
+            ("./etc", Entry::Dir),
+            ("./etc/os-release", Entry::Symlink("../usr/lib/os-release")),
+            ("./usr/lib/os-release", Entry::File("ID=debian\n")),
+        ]);
+        fs::write(&path, gzip(&rootfs)).unwrap();
+
+        assert_eq!(Format::detect(&path).unwrap(), Format::Rootfs);
+        assert_eq!(id(&path).as_deref(), Some("debian"));
+    }
+
+    #[test]
+    fn docker_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.tar");
+        let base = tar(&[
+            ("etc/os-release", Entry::File("ID=alpine\n")),
+            ("usr/lib/os-release", Entry::File("ID=debian\n")),
+        ]);
+        let top = tar(&[("etc/.wh.os-release", Entry::File(""))]);
+        let manifest =
+            r#"[{"Config":"config.json","Layers":["a/layer.tar","b/layer.tar","c/layer.tar"]}]"#;
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut append = |path: &str, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            builder.append_data(&mut header, path, data).unwrap();
+        };
+        append("manifest.json", manifest.as_bytes());
+        append("a/layer.tar", &base);
+        append("b/layer.tar", &top);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_entry_type(EntryType::Symlink);
+        builder
+            .append_link(&mut header, "c/layer.tar", "../a/layer.tar")
+            .unwrap();
+        fs::write(&path, builder.into_inner().unwrap()).unwrap();
+
+        assert_eq!(Format::detect(&path).unwrap(), Format::DockerArchive);
+        // The whiteout in the second layer hides /etc/os-release of the first, but the
+        // third layer, which is the first again, restores it.
+        assert_eq!(id(&path).as_deref(), Some("alpine"));
+
+        let manifest = manifest.replace(r#","c/layer.tar""#, "");
+        let docker = dir.path().join("unpacked");
+        for (name, data) in [
+            ("manifest.json", manifest.as_bytes()),
+            ("a/layer.tar", &base),
+            ("b/layer.tar", &top),
+        ] {
+            let path = docker.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, data).unwrap();
+        }
+        assert_eq!(Format::detect(&docker).unwrap(), Format::DockerArchive);
+        assert_eq!(id(&docker).as_deref(), Some("debian"));
+    }
+
+    #[test]
+    fn oci_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let layout = dir.path();
+        let blob = |digest: &str, data: &[u8]| {
+            let path = layout.join("blobs/sha256").join(digest);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, data).unwrap();
+        };
+
+        blob(
+            "base",
+            &gzip(&tar(&[
+                ("etc/os-release", Entry::File("ID=fedora\n")),
+                ("usr/lib/os-release", Entry::Hardlink("etc/os-release")),
+            ])),
+        );
+        blob(
+            "opaque",
+            &tar(&[
+                ("etc/.wh..wh..opq", Entry::File("")),
+                ("etc/hostname", Entry::File("container\n")),
+            ]),
+        );
+        blob(
+            "manifest",
+            br#"{"layers":[{"digest":"sha256:base"},{"digest":"sha256:opaque"}]}"#,
+        );
+        blob(
+            "attestation",
+            br#"{"layers":[{"digest":"sha256:missing"}]}"#,
+        );
+        blob(
+            "index",
+            br#"{"manifests":[
+                {"digest":"sha256:attestation","platform":{"os":"unknown"}},
+                {"digest":"sha256:manifest","platform":{"os":"linux"}}
+            ]}"#,
+        );
+        fs::write(
+            layout.join("oci-layout"),
+            r#"{"imageLayoutVersion":"1.0.0"}"#,
+        )
+        .unwrap();
+        fs::write(
+            layout.join("index.json"),
+            r#"{"manifests":[{"digest":"sha256:index"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(Format::detect(layout).unwrap(), Format::OciLayout);
+        // The opaque /etc hides the first layer's /etc/os-release, leaving its hard link.
+        assert_eq!(id(layout).as_deref(), Some("fedora"));
+
+        let archive = tempfile::NamedTempFile::new().unwrap();
+        let mut builder = tar::Builder::new(File::create(archive.path()).unwrap());
+        builder.append_dir_all(".", layout).unwrap();
+        builder.finish().unwrap();
+        assert_eq!(Format::detect(archive.path()).unwrap(), Format::OciLayout);
+        assert_eq!(id(archive.path()).as_deref(), Some("fedora"));
+
+        fs::write(
+            layout.join("index.json"),
+            r#"{"manifests":[{"digest":"sha256:../../oci-layout"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            inspect(layout).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn symlinks_stay_within_the_image() {
+        let mut tree = Tree::default();
+        let layer = tar(&[
+            (
+                "etc/os-release",
+                Entry::Symlink("../../../../usr/lib/os-release"),
+            ),
+            ("usr/lib/os-release", Entry::File("ID=arch\n")),
+            ("lib", Entry::Symlink("/usr/lib")),
+            ("loop/a", Entry::Symlink("b")),
+            ("loop/b", Entry::Symlink("a")),
+        ]);
+        tree.apply(0, &mut &layer[..]).unwrap();
+
+        assert_eq!(tree.resolve("/etc/os-release").unwrap(), Some((0, 1)));
+        assert_eq!(tree.resolve("/lib/os-release").unwrap(), Some((0, 1)));
+        assert_eq!(tree.resolve("/lib/missing").unwrap(), None);
+        assert_eq!(tree.resolve("/etc").unwrap(), None);
+        assert!(tree.resolve("/loop/a").is_err());
+    }
+
+    #[test]
+    fn nothing_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rootfs.tar");
+        fs::write(&path, tar(&[("etc/hostname", Entry::File("x\n"))])).unwrap();
+        assert_eq!(inspect(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            inspect(dir.path()).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
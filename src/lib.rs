@@ -0,0 +1,36 @@
+//! Identification of Linux distributions through their `os-release` files.
+
+pub mod ansi_color;
+pub mod cpe;
+pub mod date;
+pub mod detect;
+mod distro;
+pub mod document;
+pub mod error;
+pub mod extension;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod lifecycle;
+pub mod lsb_release;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+pub mod os_release;
+pub mod os_release_ref;
+mod parser;
+pub mod root;
+pub mod shell;
+pub mod version;
+
+pub use ansi_color::{AnsiColor, ColorSupport};
+pub use cpe::{Cpe, ParseCpeError};
+pub use date::{Date, ParseDateError};
+pub use detect::{detect, detect_in, Detection};
+pub use distro::{Distro, Family};
+pub use document::Document;
+pub use error::{Error, ParseError, ParseErrorKind};
+pub use lifecycle::Lifecycle;
+pub use lsb_release::LsbRelease;
+pub use os_release::OsRelease;
+pub use os_release_ref::OsReleaseRef;
+pub use shell::Shell;
+pub use version::{ParseVersionError, Version, VersionReq};
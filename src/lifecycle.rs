@@ -0,0 +1,304 @@
+//! Release and end-of-life dates of well-known distributions, for systems whose
+//! os-release file does not set `SUPPORT_END`.
+//!
+//! A table is built into the crate, and can be patched between releases of the
+//! crate with `Lifecycle::extend` and a local file in the same format as
+//! `data/lifecycle.tsv`.
+
+use crate::{Date, Distro, OsRelease};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The built-in table, in the format read by `Lifecycle::parse`.
+const BUILTIN: &str = include_str!("../data/lifecycle.tsv");
+
+const COLUMNS: &str = "expected the tab-separated columns ID VERSION CODENAME RELEASED EOL [lts]";
+
+/// The lifecycle of a single release of a distribution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Release {
+    /// The distribution that made the release.
+    pub distro: Distro,
+    /// The `VERSION_ID` of the release.
+    ///
+    /// **IE:** `22.04`
+    pub version: String,
+    /// The codename of the release, if it has one.
+    ///
+    /// **IE:** `jammy`
+    pub codename: Option<String>,
+    /// The day the release was made generally available.
+    pub released: Date,
+    /// The last day of standard support, as vendors announce it.
+    ///
+    /// Unlike `SUPPORT_END`, the release is still supported on this day.
+    pub eol: Date,
+    /// Whether the release is a long-term support release.
+    pub lts: bool,
+}
+
+impl Release {
+    /// Whether standard support for the release has ended as of `date`.
+    pub fn is_eol(&self, date: Date) -> bool {
+        date > self.eol
+    }
+
+    /// The day on which support ends, the day after `eol`, in the sense of
+    /// `SUPPORT_END`.
+    pub fn support_end(&self) -> Date {
+        self.eol.next_day()
+    }
+}
+
+/// A table of release lifecycles, keyed by distribution and `VERSION_ID`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lifecycle {
+    version: Option<String>,
+    releases: Vec<Release>,
+}
+
+impl Lifecycle {
+    /// The table built into this crate.
+    pub fn builtin() -> Lifecycle {
+        Lifecycle::parse(BUILTIN).expect("built-in lifecycle table is valid")
+    }
+
+    /// Read a table from the file at `path`.
+    ///
+    /// A malformed table is reported as `io::ErrorKind::InvalidData`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Lifecycle> {
+        Lifecycle::parse(&fs::read_to_string(path)?)
+            .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))
+    }
+
+    /// Parse a table of tab-separated `ID VERSION CODENAME RELEASED EOL [lts]` lines.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and `-` stands for a
+    /// release without a codename. A `version` line names the revision of the table.
+    pub fn parse(text: &str) -> Result<Lifecycle, ParseLifecycleError> {
+        let mut lifecycle = Lifecycle::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| ParseLifecycleError {
+                line: number + 1,
+                message,
+            };
+            let columns: Vec<&str> = line
+                .split('\t')
+                .map(str::trim)
+                .filter(|column| !column.is_empty())
+                .collect();
+
+            if let ["version", version] = columns[..] {
+                lifecycle.version = Some(version.to_owned());
+                continue;
+            }
+
+            let (id, version, codename, released, eol, lts) = match columns[..] {
+                [id, version, codename, released, eol] => {
+                    (id, version, codename, released, eol, false)
+                }
+                [id, version, codename, released, eol, "lts"] => {
+                    (id, version, codename, released, eol, true)
+                }
+                _ => return Err(error(COLUMNS.into())),
+            };
+
+            let date = |text: &str| text.parse::<Date>().map_err(|why| error(why.to_string()));
+            lifecycle.insert(Release {
+                distro: Distro::from_id(id),
+                version: version.to_owned(),
+                codename: (codename != "-").then(|| codename.to_owned()),
+                released: date(released)?,
+                eol: date(eol)?,
+                lts,
+            });
+        }
+
+        Ok(lifecycle)
+    }
+
+    /// The revision of the table, as given by its `version` line.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Every release in the table, in the order they were added.
+    pub fn releases(&self) -> &[Release] {
+        &self.releases
+    }
+
+    /// Add `release`, replacing any release of the same distribution and version.
+    pub fn insert(&mut self, release: Release) {
+        match self
+            .releases
+            .iter_mut()
+            .find(|known| known.distro == release.distro && known.version == release.version)
+        {
+            Some(known) => *known = release,
+            None => self.releases.push(release),
+        }
+    }
+
+    /// Add the releases of `other`, which take precedence over those already known.
+    ///
+    /// The revision of `other` replaces this one, if it has one.
+    pub fn extend(&mut self, other: Lifecycle) {
+        self.version = other.version.or(self.version.take());
+        for release in other.releases {
+            self.insert(release);
+        }
+    }
+
+    /// The release of `distro` whose version is `version_id`.
+    ///
+    /// Point releases fall back to the release they belong to, so `9.3` finds `9`
+    /// and `3.19.1` finds `3.19`.
+    pub fn get(&self, distro: &Distro, version_id: &str) -> Option<&Release> {
+        let mut version = version_id;
+        loop {
+            let found = self
+                .releases
+                .iter()
+                .find(|release| release.distro == *distro && release.version == version);
+            if found.is_some() {
+                return found;
+            }
+            version = &version[..version.rfind('.')?];
+        }
+    }
+
+    /// The release described by `os_release`.
+    pub fn lookup(&self, os_release: &OsRelease) -> Option<&Release> {
+        self.get(&os_release.distro(), os_release.version_id.as_deref()?)
+    }
+}
+
+/// A malformed line in a lifecycle table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseLifecycleError {
+    /// The line on which the problem was found, starting from 1.
+    pub line: usize,
+    /// What is wrong with the line.
+    pub message: String,
+}
+
+impl fmt::Display for ParseLifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseLifecycleError {}
+
+impl OsRelease {
+    /// The day on which support for this release ends, as given by `SUPPORT_END`
+    /// or else by `lifecycle`.
+    pub fn support_end_date_in(&self, lifecycle: &Lifecycle) -> Option<Date> {
+        self.support_end_date()
+            .or_else(|| Some(lifecycle.lookup(self)?.support_end()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input: &str) -> OsRelease {
+        OsRelease::from_iter(input.lines().map(String::from))
+    }
+
+    #[test]
+    fn builtin() {
+        let lifecycle = Lifecycle::builtin();
+        assert!(lifecycle.version().is_some());
+        for release in lifecycle.releases() {
+            assert!(
+                !matches!(release.distro, Distro::Unknown(_)),
+                "{:?}",
+                release
+            );
+            assert!(release.released < release.eol, "{:?}", release);
+        }
+
+        let jammy = lifecycle.get(&Distro::Ubuntu, "22.04").unwrap();
+        assert_eq!(jammy.codename.as_deref(), Some("jammy"));
+        assert!(jammy.lts);
+        assert!(!lifecycle.get(&Distro::Ubuntu, "23.10").unwrap().lts);
+    }
+
+    #[test]
+    fn lookup() {
+        let lifecycle = Lifecycle::builtin();
+        let rhel = parse("ID=rhel\nVERSION_ID=9.3\n");
+        assert_eq!(lifecycle.lookup(&rhel).unwrap().version, "9");
+        let alpine = parse("ID=alpine\nVERSION_ID=3.19.1\n");
+        assert_eq!(lifecycle.lookup(&alpine).unwrap().version, "3.19");
+
+        assert!(lifecycle.lookup(&parse("ID=arch\n")).is_none());
+        assert!(lifecycle
+            .lookup(&parse("ID=fedora\nVERSION_ID=1\n"))
+            .is_none());
+        // CentOS Stream shares its ID with CentOS Linux, but not its lifecycle.
+        let stream = parse("NAME=\"CentOS Stream\"\nID=centos\nVERSION_ID=9\n");
+        assert!(lifecycle.lookup(&stream).is_none());
+
+        // Red Hat supports RHEL 7 up to and including 2024-06-30.
+        let rhel = lifecycle.get(&Distro::Rhel, "7").unwrap();
+        assert!(!rhel.is_eol("2024-06-30".parse().unwrap()));
+        assert!(rhel.is_eol("2024-07-01".parse().unwrap()));
+        assert_eq!(rhel.support_end(), "2024-07-01".parse().unwrap());
+
+        let debian = parse("ID=debian\nVERSION_ID=12\n");
+        assert_eq!(
+            debian.support_end_date_in(&lifecycle),
+            "2026-06-11".parse().ok()
+        );
+        let set = parse("ID=debian\nVERSION_ID=12\nSUPPORT_END=2030-01-01\n");
+        assert_eq!(
+            set.support_end_date_in(&lifecycle),
+            "2030-01-01".parse().ok()
+        );
+    }
+
+    #[test]
+    fn extend() {
+        let mut lifecycle = Lifecycle::builtin();
+        let count = lifecycle.releases().len();
+        let local = Lifecycle::parse(
+            "# local patches\n\
+             version\tlocal-1\n\
+             debian\t12\tbookworm\t2023-06-10\t2028-06-30\n\
+             fedora\t99\t-\t2030-04-01\t2031-05-01\n",
+        )
+        .unwrap();
+        lifecycle.extend(local);
+
+        assert_eq!(lifecycle.version(), Some("local-1"));
+        assert_eq!(lifecycle.releases().len(), count + 1);
+        let bookworm = lifecycle.get(&Distro::Debian, "12").unwrap();
+        assert_eq!(bookworm.eol, "2028-06-30".parse().unwrap());
+        let fedora = lifecycle.get(&Distro::Fedora, "99").unwrap();
+        assert_eq!(fedora.codename, None);
+    }
+
+    #[test]
+    fn errors() {
+        let why = Lifecycle::parse("fedora\t40\t-\t2024-04-23\n").unwrap_err();
+        assert_eq!(why.line, 1);
+        let why = Lifecycle::parse("\nfedora\t40\t-\t2024-04-23\t2025-5-13\n").unwrap_err();
+        assert_eq!(
+            why.to_string(),
+            "line 2: `2025-5-13` is not a valid YYYY-MM-DD date"
+        );
+        assert!(Lifecycle::parse("fedora\t40\t-\t2024-04-23\t2025-05-13\tlong\n").is_err());
+    }
+}
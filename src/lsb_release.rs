@@ -0,0 +1,240 @@
+use crate::document::Document;
+use crate::OsRelease;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// Location of the lsb-release file.
+pub const LSB_RELEASE_PATH: &str = "/etc/lsb-release";
+
+/// Contents of the `/etc/lsb-release` file, as a data structure.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LsbRelease {
+    /// The name of the distributor.
+    ///
+    /// **IE:** `Ubuntu`
+    pub distrib_id: Option<String>,
+    /// The version of this release.
+    ///
+    /// **IE:** `22.04`
+    pub distrib_release: Option<String>,
+    /// The codename of this release.
+    ///
+    /// **IE:** `jammy`
+    pub distrib_codename: Option<String>,
+    /// A description of this release.
+    ///
+    /// **IE:** `Ubuntu 22.04.3 LTS`
+    pub distrib_description: Option<String>,
+    /// Additional keys, such as `LSB_VERSION`.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl LsbRelease {
+    /// Attempt to parse the contents of `/etc/lsb-release`.
+    pub fn new() -> io::Result<LsbRelease> {
+        Self::from_file(LSB_RELEASE_PATH)
+    }
+
+    /// Attempt to parse any `/etc/lsb-release`-like file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<LsbRelease> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Parse the contents of an lsb-release file, which uses the same syntax as os-release.
+    pub fn parse(text: &str) -> LsbRelease {
+        let mut lsb_release = LsbRelease::default();
+        for (key, value) in Document::parse(text).entries() {
+            let field = match key {
+                "DISTRIB_ID" => &mut lsb_release.distrib_id,
+                "DISTRIB_RELEASE" => &mut lsb_release.distrib_release,
+                "DISTRIB_CODENAME" => &mut lsb_release.distrib_codename,
+                "DISTRIB_DESCRIPTION" => &mut lsb_release.distrib_description,
+                key => {
+                    lsb_release.extra.insert(key.to_owned(), value.to_owned());
+                    continue;
+                }
+            };
+
+            *field = Some(value.to_owned());
+        }
+        lsb_release
+    }
+
+    /// The os-release view of this data.
+    ///
+    /// `ID` is derived from `DISTRIB_ID` by lowercasing it, which matches the IDs of
+    /// the distributions that ship an lsb-release file, such as `ubuntu`.
+    pub fn to_os_release(&self) -> OsRelease {
+        OsRelease {
+            id: self
+                .distrib_id
+                .as_ref()
+                .map(|id| id.to_lowercase().replace(' ', "-")),
+            name: self.distrib_id.clone(),
+            version_id: self.distrib_release.clone(),
+            version_codename: self.distrib_codename.clone(),
+            pretty_name: self.distrib_description.clone(),
+            ..OsRelease::default()
+        }
+    }
+}
+
+/// Derives the fields that `lsb_release` reports from os-release data, the way
+/// distributions without an lsb-release file do.
+///
+/// The distributor ID is the first word of `NAME`, such as `Fedora` for
+/// `Fedora Linux`.
+impl From<&OsRelease> for LsbRelease {
+    fn from(os_release: &OsRelease) -> Self {
+        let distrib_id = os_release
+            .name
+            .as_deref()
+            .and_then(|name| name.split_whitespace().next())
+            .or(os_release.id.as_deref())
+            .map(String::from);
+
+        LsbRelease {
+            distrib_id,
+            distrib_release: os_release.version_id.clone(),
+            distrib_codename: os_release
+                .version_codename
+                .clone()
+                .filter(|c| !c.is_empty()),
+            distrib_description: os_release.pretty_name.clone(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Which fields `lsb_release` should print.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LsbFields {
+    /// The LSB modules that the system supports, as selected by `-v`.
+    pub version: bool,
+    /// The distributor ID, as selected by `-i`.
+    pub id: bool,
+    /// The description of the distribution, as selected by `-d`.
+    pub description: bool,
+    /// The release number, as selected by `-r`.
+    pub release: bool,
+    /// The codename of the release, as selected by `-c`.
+    pub codename: bool,
+}
+
+impl LsbFields {
+    /// Every field, as selected by `lsb_release -a`.
+    pub const ALL: LsbFields = LsbFields {
+        version: true,
+        id: true,
+        description: true,
+        release: true,
+        codename: true,
+    };
+}
+
+impl LsbRelease {
+    /// The report that `lsb_release` prints for `fields`, in its order and format.
+    ///
+    /// In `short` form only the values are printed, one per line. Missing values are
+    /// printed as `n/a`. The LSB version is taken from `LSB_VERSION`; when it is unset,
+    /// `lsb_release` prints a notice to stderr instead, which is returned separately.
+    pub fn report(&self, fields: LsbFields, short: bool) -> (String, Option<&'static str>) {
+        let mut report = String::new();
+        let mut notice = None;
+        let mut line = |label: &str, value: Option<&str>| {
+            let value = value.unwrap_or("n/a");
+            if short {
+                report.push_str(value);
+            } else {
+                report.push_str(label);
+                report.push_str(":\t");
+                report.push_str(value);
+            }
+            report.push('\n');
+        };
+
+        if fields.version {
+            match self.extra.get("LSB_VERSION") {
+                Some(version) => line("LSB Version", Some(version)),
+                None => notice = Some("No LSB modules are available."),
+            }
+        }
+        if fields.id {
+            line("Distributor ID", self.distrib_id.as_deref());
+        }
+        if fields.description {
+            line("Description", self.distrib_description.as_deref());
+        }
+        if fields.release {
+            line("Release", self.distrib_release.as_deref());
+        }
+        if fields.codename {
+            line("Codename", self.distrib_codename.as_deref());
+        }
+
+        (report, notice)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const UBUNTU: &str = "DISTRIB_ID=Ubuntu\nDISTRIB_RELEASE=22.04\nDISTRIB_CODENAME=jammy\n\
+        DISTRIB_DESCRIPTION=\"Ubuntu 22.04.3 LTS\"\n";
+
+    #[test]
+    fn parse() {
+        let lsb_release = LsbRelease::parse(UBUNTU);
+        assert_eq!(lsb_release.distrib_id.as_deref(), Some("Ubuntu"));
+        assert_eq!(lsb_release.distrib_release.as_deref(), Some("22.04"));
+        assert_eq!(lsb_release.distrib_codename.as_deref(), Some("jammy"));
+        assert_eq!(
+            lsb_release.distrib_description.as_deref(),
+            Some("Ubuntu 22.04.3 LTS")
+        );
+
+        let os_release = lsb_release.to_os_release();
+        assert_eq!(os_release.id.as_deref(), Some("ubuntu"));
+        assert_eq!(os_release.version_id.as_deref(), Some("22.04"));
+    }
+
+    #[test]
+    fn from_os_release() {
+        let fedora = include_str!("../fedora-rawhide-os-release");
+        let os_release = OsRelease::from_iter(fedora.lines().map(String::from));
+        let lsb_release = LsbRelease::from(&os_release);
+        assert_eq!(lsb_release.distrib_id.as_deref(), Some("Fedora"));
+        assert_eq!(lsb_release.distrib_release.as_deref(), Some("38"));
+        assert_eq!(lsb_release.distrib_codename, None);
+    }
+
+    #[test]
+    fn report() {
+        let lsb_release = LsbRelease::parse(UBUNTU);
+        assert_eq!(
+            lsb_release.report(LsbFields::ALL, false),
+            (
+                "Distributor ID:\tUbuntu\nDescription:\tUbuntu 22.04.3 LTS\n\
+                 Release:\t22.04\nCodename:\tjammy\n"
+                    .to_owned(),
+                Some("No LSB modules are available.")
+            )
+        );
+
+        let fields = LsbFields {
+            release: true,
+            codename: true,
+            ..LsbFields::default()
+        };
+        assert_eq!(lsb_release.report(fields, true).0, "22.04\njammy\n");
+
+        let empty = LsbRelease::default();
+        let fields = LsbFields {
+            codename: true,
+            ..LsbFields::default()
+        };
+        assert_eq!(empty.report(fields, false).0, "Codename:\tn/a\n");
+    }
+}
@@ -1,8 +1,443 @@
-mod os_release;
+use distro::lsb_release::LsbFields;
+use distro::{ColorSupport, Date, Lifecycle, LsbRelease, OsRelease, Shell, VersionReq};
+use serde::Serializer;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-fn main() {
-    let os_release = os_release::OsRelease::new().unwrap();
-    println!("{:?}", os_release);
-    let os_release = os_release::OsRelease::from_file("/etc/os-release").unwrap();
-    println!("{:?}", os_release);
+const USAGE: &str = "\
+Usage: distro [OPTIONS] [COMMAND]
+
+Commands:
+  all                Print every key as os-release assignments (the default)
+  get KEY...         Print the value of each KEY, failing if any is missing
+  is ID              Test whether the os-release ID is ID; `is ID-like` also matches
+                     distributions that list ID in ID_LIKE
+  version [REQ]      Print VERSION_ID, or test it against a requirement such as \">= 20.04\"
+  eol [DATE]         Report when support ends, as set by SUPPORT_END or else the built-in
+                     lifecycle table, failing if it has ended by DATE (YYYY-MM-DD,
+                     default: today)
+  summary            Print a neofetch-style summary line, with the name in ANSI_COLOR
+  lsb_release [-aidrcsv]
+                     Print what `lsb_release` would; the same happens when the binary
+                     is invoked as `lsb_release`
+
+Options:
+  --root DIR         Inspect the system whose root directory is DIR, falling back to
+                     legacy release files such as /etc/redhat-release
+  --file PATH        Read PATH instead of searching for os-release
+  --lifecycle FILE   Add the releases in FILE to the lifecycle table used by `eol`,
+                     replacing those of the same version
+  --json             Print `all` as a JSON object
+  --export           Print `all` as shell assignments, to be used with `eval`
+  --prefix PREFIX    Prepend PREFIX to the variable names of `--export`
+  --shell SHELL      Quote `--export` for SHELL: sh, bash, zsh or fish (default: sh)
+  --color WHEN       Color `summary`: auto, always or never (default: auto, which
+                     honors NO_COLOR and only colors a terminal)
+  -h, --help         Print this help
+
+Tests exit with 0 when they hold and 1 when they do not. Errors exit with 2.";
+
+const LSB_USAGE: &str = "\
+Usage: lsb_release [OPTIONS]
+
+Options:
+  -v, --version      Print the LSB version (the default)
+  -i, --id           Print the distributor ID
+  -d, --description  Print the description of the distribution
+  -r, --release      Print the release number
+  -c, --codename     Print the codename
+  -a, --all          Print all of the above
+  -s, --short        Print only the values, without labels
+  -h, --help         Print this help";
+
+/// Where to read the os-release data from.
+enum Source {
+    Root(PathBuf),
+    File(PathBuf),
+}
+
+/// When `summary` is colored.
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+/// How `all` prints the keys.
+enum Format {
+    OsRelease,
+    Json,
+    Shell,
+}
+
+enum Command {
+    All,
+    Get(Vec<String>),
+    Is(String),
+    Version(Option<String>),
+    Eol(Option<String>),
+    Summary,
+    LsbRelease { fields: LsbFields, short: bool },
+}
+
+struct Args {
+    source: Source,
+    format: Format,
+    shell: Shell,
+    prefix: String,
+    lifecycle: Option<PathBuf>,
+    color: ColorWhen,
+    command: Command,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            source: Source::Root(PathBuf::from("/")),
+            format: Format::OsRelease,
+            shell: Shell::Posix,
+            prefix: String::new(),
+            lifecycle: None,
+            color: ColorWhen::Auto,
+            command: Command::All,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args();
+    let invoked_as = args.next().unwrap_or_default();
+
+    // Installed as `lsb_release`, behave as that command does.
+    let (args, usage) = if Path::new(&invoked_as).file_name() == Some("lsb_release".as_ref()) {
+        let args = parse_lsb_release(args).map(|command| {
+            command.map(|command| Args {
+                command,
+                ..Args::default()
+            })
+        });
+        (args, LSB_USAGE)
+    } else {
+        (parse_args(args), USAGE)
+    };
+
+    let args = match args {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", usage);
+            return ExitCode::SUCCESS;
+        }
+        Err(why) => {
+            eprintln!("distro: {}\n\n{}", why, usage);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(why) => {
+            eprintln!("distro: {}", why);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Parse the command line, returning `None` if help was requested.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Args>, String> {
+    let Args {
+        mut source,
+        mut format,
+        mut shell,
+        mut prefix,
+        mut lifecycle,
+        mut color,
+        ..
+    } = Args::default();
+    let mut words = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} requires a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--root" => source = Source::Root(value("--root")?.into()),
+            "--file" => source = Source::File(value("--file")?.into()),
+            "--lifecycle" => lifecycle = Some(value("--lifecycle")?.into()),
+            "--json" => format = Format::Json,
+            "--export" => format = Format::Shell,
+            "--prefix" => prefix = value("--prefix")?,
+            "--color" => {
+                color = match value("--color")?.as_str() {
+                    "auto" => ColorWhen::Auto,
+                    "always" => ColorWhen::Always,
+                    "never" => ColorWhen::Never,
+                    other => return Err(format!("`{}` is not auto, always or never", other)),
+                }
+            }
+            "--shell" => {
+                shell = value("--shell")?
+                    .parse()
+                    .map_err(|why: distro::shell::UnknownShell| why.to_string())?
+            }
+            option if option.starts_with('-') && option.len() > 1 => {
+                return Err(format!("unknown option `{}`", option))
+            }
+            "lsb_release" if words.is_empty() => {
+                let Some(command) = parse_lsb_release(args.by_ref())? else {
+                    return Ok(None);
+                };
+                return Ok(Some(Args {
+                    source,
+                    command,
+                    ..Args::default()
+                }));
+            }
+            _ => words.push(arg),
+        }
+    }
+
+    let mut words = words.into_iter();
+    let command = match words.next().as_deref() {
+        None | Some("all") => Command::All,
+        Some("get") => {
+            let keys: Vec<String> = words.by_ref().collect();
+            if keys.is_empty() {
+                return Err("`get` requires at least one key".into());
+            }
+            Command::Get(keys)
+        }
+        Some("is") => Command::Is(words.next().ok_or("`is` requires an ID")?),
+        Some("version") => Command::Version(words.next()),
+        Some("eol") => Command::Eol(words.next()),
+        Some("summary") => Command::Summary,
+        Some(other) => return Err(format!("unknown command `{}`", other)),
+    };
+
+    if let Some(extra) = words.next() {
+        return Err(format!("unexpected argument `{}`", extra));
+    }
+
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if prefix.starts_with(|c: char| c.is_ascii_digit()) || !prefix.chars().all(is_name) {
+        return Err(format!("`{}` is not a valid variable name prefix", prefix));
+    }
+
+    Ok(Some(Args {
+        source,
+        format,
+        shell,
+        prefix,
+        lifecycle,
+        color,
+        command,
+    }))
+}
+
+/// Parse the options of `lsb_release`, returning `None` if help was requested.
+fn parse_lsb_release<I: Iterator<Item = String>>(args: I) -> Result<Option<Command>, String> {
+    let mut fields = LsbFields::default();
+    let mut short = false;
+
+    for arg in args {
+        let flags: Vec<char> = match arg.as_str() {
+            "--version" => vec!['v'],
+            "--id" => vec!['i'],
+            "--description" => vec!['d'],
+            "--release" => vec!['r'],
+            "--codename" => vec!['c'],
+            "--all" => vec!['a'],
+            "--short" => vec!['s'],
+            "--help" => vec!['h'],
+            flags if flags.starts_with('-') && !flags.starts_with("--") && flags.len() > 1 => {
+                flags[1..].chars().collect()
+            }
+            other => return Err(format!("unexpected argument `{}`", other)),
+        };
+
+        for flag in flags {
+            match flag {
+                'v' => fields.version = true,
+                'i' => fields.id = true,
+                'd' => fields.description = true,
+                'r' => fields.release = true,
+                'c' => fields.codename = true,
+                'a' => fields = LsbFields::ALL,
+                's' => short = true,
+                'h' => return Ok(None),
+                other => return Err(format!("unknown option `-{}`", other)),
+            }
+        }
+    }
+
+    // As with `lsb_release`, only the LSB version is reported when nothing is selected.
+    if fields == LsbFields::default() {
+        fields.version = true;
+    }
+
+    Ok(Some(Command::LsbRelease { fields, short }))
+}
+
+/// Carry out the command, returning whether it succeeded or its test held.
+fn run(args: Args) -> Result<bool, String> {
+    let os_release = match &args.source {
+        Source::Root(root) => distro::detect_in(root)
+            .map(|detection| detection.os_release)
+            .map_err(|why| format!("failed to read os-release in {}: {}", root.display(), why))?,
+        Source::File(path) => OsRelease::from_file(path)
+            .map_err(|why| format!("failed to read {}: {}", path.display(), why))?,
+    };
+
+    match args.command {
+        Command::LsbRelease { fields, short } => {
+            let mut lsb_release = LsbRelease::from(&os_release);
+            // Values from an lsb-release file take precedence, as they do for `lsb_release`.
+            if let Source::Root(root) = &args.source {
+                if let Ok(file) =
+                    distro::root::resolve(root, "/etc/lsb-release").and_then(LsbRelease::from_file)
+                {
+                    lsb_release = LsbRelease {
+                        distrib_id: file.distrib_id.or(lsb_release.distrib_id),
+                        distrib_release: file.distrib_release.or(lsb_release.distrib_release),
+                        distrib_codename: file.distrib_codename.or(lsb_release.distrib_codename),
+                        distrib_description: file
+                            .distrib_description
+                            .or(lsb_release.distrib_description),
+                        extra: file.extra,
+                    };
+                }
+            }
+
+            let (report, notice) = lsb_release.report(fields, short);
+            if let Some(notice) = notice {
+                eprintln!("{}", notice);
+            }
+            print!("{}", report);
+        }
+        Command::All => match args.format {
+            Format::OsRelease => print!("{}", os_release),
+            Format::Json => {
+                print_json(&os_release).map_err(|why| format!("failed to write JSON: {}", why))?
+            }
+            Format::Shell => print!("{}", os_release.to_shell(args.shell, &args.prefix)),
+        },
+        Command::Summary => {
+            let support = match args.color {
+                ColorWhen::Never => ColorSupport::None,
+                ColorWhen::Auto if !io::stdout().is_terminal() => ColorSupport::None,
+                ColorWhen::Auto => ColorSupport::detect(),
+                // An explicit request overrides NO_COLOR, but not what the terminal can show.
+                ColorWhen::Always => match ColorSupport::from_term(
+                    env::var("COLORTERM").ok().as_deref(),
+                    env::var("TERM").ok().as_deref(),
+                ) {
+                    ColorSupport::None => ColorSupport::Ansi16,
+                    support => support,
+                },
+            };
+            let architecture = os_release
+                .architecture
+                .as_deref()
+                .unwrap_or(env::consts::ARCH);
+            println!(
+                "OS: {} {}",
+                os_release.render_pretty_name(support),
+                architecture
+            );
+        }
+        Command::Get(keys) => {
+            let mut found = true;
+            for key in keys {
+                match os_release.get(&key) {
+                    Some(value) => println!("{}", value),
+                    None => {
+                        eprintln!("distro: {} is not set", key);
+                        found = false;
+                    }
+                }
+            }
+            return Ok(found);
+        }
+        Command::Is(id) => {
+            let id = id.to_lowercase();
+            return Ok(match id.strip_suffix("-like") {
+                Some(id) => os_release.is_like(id),
+                None => os_release.ids().next() == Some(id.as_str()),
+            });
+        }
+        Command::Version(None) => {
+            let version = os_release.release_version().ok_or_else(|| {
+                format!(
+                    "VERSION_ID `{}` is not a version",
+                    os_release.version_id.as_deref().unwrap_or_default()
+                )
+            })?;
+            println!("{}", version);
+        }
+        Command::Version(Some(requirement)) => {
+            let requirement = requirement
+                .parse::<VersionReq>()
+                .map_err(|why| why.to_string())?;
+            return Ok(os_release
+                .release_version()
+                .is_some_and(|version| requirement.matches(&version)));
+        }
+        Command::Eol(date) => {
+            let today = match date {
+                Some(date) => date.parse::<Date>().map_err(|why| why.to_string())?,
+                None => Date::today(),
+            };
+            let mut lifecycle = Lifecycle::builtin();
+            if let Some(path) = &args.lifecycle {
+                lifecycle.extend(
+                    Lifecycle::from_file(path)
+                        .map_err(|why| format!("failed to read {}: {}", path.display(), why))?,
+                );
+            }
+
+            let support_end = match os_release.support_end.as_deref() {
+                Some(support_end) => support_end
+                    .parse::<Date>()
+                    .map_err(|why| format!("SUPPORT_END: {}", why))?,
+                None => match lifecycle.lookup(&os_release) {
+                    Some(release) => release.support_end(),
+                    None => {
+                        println!("end of support is unknown");
+                        return Ok(true);
+                    }
+                },
+            };
+
+            let days = today.days_until(support_end);
+            let plural = |days: i64| if days == 1 { "day" } else { "days" };
+            // As with systemd, support has ended once SUPPORT_END is reached.
+            match days {
+                1.. => println!(
+                    "support ends on {} ({} {} left)",
+                    support_end,
+                    days,
+                    plural(days)
+                ),
+                0 => println!("support ended on {} (today)", support_end),
+                _ => println!(
+                    "support ended on {} ({} {} ago)",
+                    support_end,
+                    -days,
+                    plural(-days)
+                ),
+            }
+            return Ok(days > 0);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Write every key of `os_release` to stdout as a flat JSON object, in the order of
+/// `OsRelease::entries`.
+fn print_json(os_release: &OsRelease) -> io::Result<()> {
+    let mut json = serde_json::Serializer::new(io::stdout().lock());
+    json.collect_map(os_release.entries())?;
+    writeln!(json.into_inner())
 }
@@ -0,0 +1,116 @@
+//! Async counterparts of the `OsRelease` constructors, for use within a tokio runtime.
+//!
+//! Files are read with `tokio::fs` and then parsed in memory by the same parser as the
+//! blocking constructors, so both always agree on the result.
+
+use crate::error::Error;
+use crate::os_release;
+use crate::{root, OsRelease};
+use std::io;
+use std::path::{Path, PathBuf};
+
+impl OsRelease {
+    /// Like `OsRelease::new`, without blocking the runtime.
+    pub async fn new_async() -> io::Result<OsRelease> {
+        Self::discover_async()
+            .await
+            .map(|(_, os_release)| os_release)
+    }
+
+    /// Like `OsRelease::discover`, without blocking the runtime.
+    pub async fn discover_async() -> io::Result<(PathBuf, OsRelease)> {
+        Self::discover_in_async("/").await
+    }
+
+    /// Like `OsRelease::discover_in`, without blocking the runtime.
+    pub async fn discover_in_async<P: AsRef<Path>>(root: P) -> io::Result<(PathBuf, OsRelease)> {
+        let root = root.as_ref();
+        let mut not_found = None;
+        for (path, prefixed) in os_release::candidates(root) {
+            match read_in(root, path).await {
+                Ok(bytes) => return Ok((prefixed, OsRelease::from_bytes(&bytes)?)),
+                Err(why) if why.kind() == io::ErrorKind::NotFound => not_found = Some(why),
+                Err(why) => return Err(why),
+            }
+        }
+
+        Err(not_found.expect("OS_RELEASE_PATHS is not empty"))
+    }
+
+    /// Like `OsRelease::from_file`, without blocking the runtime.
+    pub async fn from_file_async<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
+        Ok(OsRelease::from_bytes(&tokio::fs::read(path).await?)?)
+    }
+
+    /// Like `OsRelease::from_file_in`, without blocking the runtime.
+    pub async fn from_file_in_async<P: AsRef<Path>, Q: AsRef<Path>>(
+        root: P,
+        path: Q,
+    ) -> io::Result<OsRelease> {
+        Ok(OsRelease::from_bytes(
+            &read_in(root.as_ref(), path.as_ref()).await?,
+        )?)
+    }
+
+    /// Like `OsRelease::from_file_strict`, without blocking the runtime.
+    pub async fn from_file_strict_async<P: AsRef<Path>>(path: P) -> Result<OsRelease, Error> {
+        OsRelease::from_bytes_strict(&tokio::fs::read(path).await?)
+    }
+}
+
+/// Read the file at `path` on the system whose root directory is `root`.
+async fn read_in<P: AsRef<Path>>(root: &Path, path: P) -> io::Result<Vec<u8>> {
+    let (root, path) = (root.to_path_buf(), path.as_ref().to_path_buf());
+    let resolved = tokio::task::spawn_blocking(move || root::resolve(root, path))
+        .await
+        .map_err(io::Error::other)??;
+    tokio::fs::read(resolved).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FEDORA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fedora-rawhide-os-release");
+
+    #[tokio::test]
+    async fn matches_blocking() {
+        assert_eq!(
+            OsRelease::from_file_async(FEDORA).await.unwrap(),
+            OsRelease::from_file(FEDORA).unwrap()
+        );
+        assert_eq!(
+            OsRelease::from_file_strict_async(FEDORA).await.unwrap(),
+            OsRelease::from_file_strict(FEDORA).unwrap()
+        );
+        assert!(OsRelease::from_file_async("/nonexistent").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn discover_in() {
+        let roots: Vec<_> = ["ID=first\n", "ID=second\n"]
+            .iter()
+            .map(|contents| {
+                let root = tempfile::tempdir().unwrap();
+                std::fs::create_dir_all(root.path().join("usr/lib")).unwrap();
+                std::fs::write(root.path().join("usr/lib/os-release"), contents).unwrap();
+                root
+            })
+            .collect();
+
+        let (first, second) = tokio::join!(
+            OsRelease::discover_in_async(roots[0].path()),
+            OsRelease::discover_in_async(roots[1].path()),
+        );
+        let (path, first) = first.unwrap();
+        assert_eq!(path, roots[0].path().join("usr/lib/os-release"));
+        assert_eq!(first.id.as_deref(), Some("first"));
+        assert_eq!(second.unwrap().1.id.as_deref(), Some("second"));
+
+        let empty = tempfile::tempdir().unwrap();
+        let why = OsRelease::discover_in_async(empty.path())
+            .await
+            .unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -1,109 +1,656 @@
-use std::io::BufRead;
+use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::parser::{self, Line};
+use crate::root;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
 use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-macro_rules! parse_os_release_line {
-    ($line:expr, { $($regex:expr => $value:expr),+ }) => {
-        {
-            $(
-                if let Some(caps) = $regex.captures($line) {
-                    $value = caps.get(1).unwrap().as_str().to_string();
-                    continue;
-                }
-            )+
+/// Locations of the os-release file, in the order that os-release(5) says to consult them.
+///
+/// The first one that exists is used, even if a later one also exists.
+pub const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+/// Location of the file that takes the place of os-release within an initrd.
+pub const INITRD_RELEASE_PATH: &str = "/etc/initrd-release";
+
+/// Invoke `$callback!` with the table of modeled keys and the fields that hold them,
+/// in the order of `KEYS`.
+///
+/// `ID_LIKE` holds a list rather than a single value, so it is passed on its own
+/// between the keys that come before and after it. Any tokens in parentheses after
+/// the callback are passed through first.
+macro_rules! with_keys {
+    ($callback:ident($($arg:tt)*)) => {
+        $callback! {
+            ($($arg)*)
+            [
+                name: "NAME",
+                id: "ID",
+            ]
+            id_like: "ID_LIKE",
+            [
+                pretty_name: "PRETTY_NAME",
+                cpe_name: "CPE_NAME",
+                variant: "VARIANT",
+                variant_id: "VARIANT_ID",
+                version: "VERSION",
+                version_id: "VERSION_ID",
+                version_codename: "VERSION_CODENAME",
+                platform_id: "PLATFORM_ID",
+                build_id: "BUILD_ID",
+                image_id: "IMAGE_ID",
+                image_version: "IMAGE_VERSION",
+                release_type: "RELEASE_TYPE",
+                home_url: "HOME_URL",
+                documentation_url: "DOCUMENTATION_URL",
+                support_url: "SUPPORT_URL",
+                bug_report_url: "BUG_REPORT_URL",
+                privacy_policy_url: "PRIVACY_POLICY_URL",
+                support_end: "SUPPORT_END",
+                logo: "LOGO",
+                ansi_color: "ANSI_COLOR",
+                vendor_name: "VENDOR_NAME",
+                vendor_url: "VENDOR_URL",
+                experiment: "EXPERIMENT",
+                experiment_url: "EXPERIMENT_URL",
+                default_hostname: "DEFAULT_HOSTNAME",
+                architecture: "ARCHITECTURE",
+                sysext_level: "SYSEXT_LEVEL",
+                confext_level: "CONFEXT_LEVEL",
+                sysext_scope: "SYSEXT_SCOPE",
+                confext_scope: "CONFEXT_SCOPE",
+                portable_prefixes: "PORTABLE_PREFIXES",
+            ]
+        }
+    };
+}
+
+pub(crate) use with_keys;
+
+/// Generates a method that finds the field holding a single-valued key, whose values
+/// are of type `$value`.
+macro_rules! field_mut {
+    (($value:ty) [$($before:ident: $b:literal,)*] $id_like:ident: $l:literal, [$($after:ident: $a:literal,)*]) => {
+        /// The field for `key`, or `None` if it is `ID_LIKE` or not modeled.
+        fn field_mut(&mut self, key: &str) -> Option<&mut Option<$value>> {
+            Some(match key {
+                $($b => &mut self.$before,)*
+                $($a => &mut self.$after,)*
+                _ => return None,
+            })
+        }
+    };
+}
+
+pub(crate) use field_mut;
+
+macro_rules! field {
+    (() [$($before:ident: $b:literal,)*] $id_like:ident: $l:literal, [$($after:ident: $a:literal,)*]) => {
+        /// The field for `key`, or `None` if it is `ID_LIKE` or not modeled.
+        fn field(&self, key: &str) -> Option<&Option<String>> {
+            Some(match key {
+                $($b => &self.$before,)*
+                $($a => &self.$after,)*
+                _ => return None,
+            })
         }
     };
 }
 
+macro_rules! keys {
+    (() [$($before:ident: $b:literal,)*] $id_like:ident: $l:literal, [$($after:ident: $a:literal,)*]) => {
+        /// The keys modeled by `OsRelease`, in the order they are written out.
+        ///
+        /// This follows the order in which os-release(5) documents them.
+        pub const KEYS: [&str; 34] = [$($b,)* $l, $($a,)*];
+    };
+}
+
+with_keys!(keys());
+
 /// Contents of the `/etc/os-release` file, as a data structure.
+///
+/// Every key defined by os-release(5) has its own field, which is `None` when the
+/// key is absent. Where the specification gives a default, such as `linux` for `ID`,
+/// it is not filled in.
+///
+/// With the `serde` feature, this serializes with its field names, as in
+/// `{"id": "fedora", "id_like": [], ...}`. The `keys` module serializes it with
+/// the os-release key names instead.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct OsRelease {
+    /// The CPU architecture that this OS release is built for.
+    ///
+    /// **IE:** `x86-64`
+    pub architecture: Option<String>,
+    /// An ANSI escape sequence suggested for presenting the OS name.
+    ///
+    /// **IE:** `0;38;2;60;110;180`
+    pub ansi_color: Option<String>,
     /// The URL where bugs should be reported for this OS.
-    pub bug_report_url: String,
+    pub bug_report_url: Option<String>,
+    /// A string identifying the OS build, which may change with every update.
+    ///
+    /// **IE:** `2013-03-20.3`
+    pub build_id: Option<String>,
+    /// The API level of the OS for configuration extension images.
+    pub confext_level: Option<String>,
+    /// Where configuration extension images may be used, such as `system` or `portable`.
+    pub confext_scope: Option<String>,
+    /// The CPE name of this OS release.
+    ///
+    /// **IE:** `cpe:/o:fedoraproject:fedora:38`
+    pub cpe_name: Option<String>,
+    /// The hostname to use when none has been configured.
+    ///
+    /// **IE:** `fedora`
+    pub default_hostname: Option<String>,
+    /// The URL of the main documentation for this OS.
+    pub documentation_url: Option<String>,
+    /// Marks this OS release as an experimental build, describing the experiment.
+    pub experiment: Option<String>,
+    /// The URL with more information about the experiment.
+    pub experiment_url: Option<String>,
     /// The homepage of this OS.
-    pub home_url: String,
-    /// Identifier of the original upstream OS that this release is a derivative of.
+    pub home_url: Option<String>,
+    /// Identifiers of the upstream OSes that this release is a derivative of, with the
+    /// most closely related first.
     ///
-    /// **IE:** `debian`
-    pub id_like: String,
+    /// **IE:** `["rhel", "centos", "fedora"]`
+    pub id_like: Vec<String>,
     /// An identifier which describes this release, such as `ubuntu`.
     ///
     /// **IE:** `ubuntu`
-    pub id: String,
+    pub id: Option<String>,
+    /// An identifier of the image that this OS was installed from.
+    ///
+    /// **IE:** `vendorx-cashier-system`
+    pub image_id: Option<String>,
+    /// The version of the image identified by `image_id`.
+    ///
+    /// **IE:** `33`
+    pub image_version: Option<String>,
+    /// The name of an icon representing this OS.
+    ///
+    /// **IE:** `fedora-logo-icon`
+    pub logo: Option<String>,
     /// The name of this release, without the version string.
     ///
     /// **IE:** `Ubuntu`
-    pub name: String,
-    /// The name of this release, with th eversion stirng.
+    pub name: Option<String>,
+    /// The platform identifier used by Fedora and RHEL derivatives for module streams.
+    ///
+    /// This is not part of os-release(5), but is common enough to be modeled.
+    ///
+    /// **IE:** `platform:f38`
+    pub platform_id: Option<String>,
+    /// Prefixes of the portable service images that are meant for this OS.
+    pub portable_prefixes: Option<String>,
+    /// The name of this release, with the version string.
     ///
     /// **IE:** `Ubuntu 18.04 LTS`
-    pub pretty_name: String,
+    pub pretty_name: Option<String>,
     /// The URL describing this OS's privacy policy.
-    pub privacy_policy_url: String,
+    pub privacy_policy_url: Option<String>,
+    /// The kind of release, such as `stable`, `lts`, `development` or `experimental`.
+    pub release_type: Option<String>,
+    /// The date on which support for this release ends.
+    ///
+    /// **IE:** `2024-05-14`
+    pub support_end: Option<String>,
     /// The URL for seeking support with this OS release.
-    pub support_url: String,
+    pub support_url: Option<String>,
+    /// The API level of the OS for system extension images.
+    pub sysext_level: Option<String>,
+    /// Where system extension images may be used, such as `system` or `initrd`.
+    pub sysext_scope: Option<String>,
+    /// The name of the edition or variant of this OS release.
+    ///
+    /// **IE:** `Workstation Edition`
+    pub variant: Option<String>,
+    /// An identifier of the edition or variant of this OS release.
+    ///
+    /// **IE:** `workstation`
+    pub variant_id: Option<String>,
+    /// The name of the vendor of this OS.
+    pub vendor_name: Option<String>,
+    /// The homepage of the vendor of this OS.
+    pub vendor_url: Option<String>,
     /// The codename of this version.
     ///
     /// **IE:** `bionic`
-    pub version_codename: String,
-    /// The version of this OS release, with additional details about the release.
-    ///
-    /// **IE:** `18.04 LTS (Bionic Beaver)`
-    pub version_id: String,
+    pub version_codename: Option<String>,
     /// The version of this OS release.
     ///
     /// **IE:** `18.04`
-    pub version: String,
+    pub version_id: Option<String>,
+    /// The version of this OS release, with additional details about the release.
+    ///
+    /// **IE:** `18.04 LTS (Bionic Beaver)`
+    pub version: Option<String>,
     /// Additional keys not covered by the API.
     pub extra: std::collections::BTreeMap<String, String>,
 }
 
 impl OsRelease {
-    /// Attempt to parse the contents of `/etc/os-release`.
-    pub fn new() -> std::io::Result<OsRelease> {
-        let file = std::io::BufReader::new(std::fs::File::open("/etc/os-release")?);
-        let lines = file.lines().flat_map(|line| line);
-        Ok(OsRelease::from_iter(lines))
+    /// Attempt to parse the os-release file of the running system.
+    ///
+    /// `/etc/os-release` is preferred, and `/usr/lib/os-release` is used if it does not exist.
+    pub fn new() -> io::Result<OsRelease> {
+        Self::discover().map(|(_, os_release)| os_release)
+    }
+
+    /// Like `OsRelease::new`, but also returns the path that the data was read from.
+    pub fn discover() -> io::Result<(PathBuf, OsRelease)> {
+        Self::discover_in("/")
+    }
+
+    /// Locate and parse the os-release file of a system whose root directory is `root`,
+    /// such as a mounted image or chroot.
+    ///
+    /// The returned path includes the `root` prefix.
+    pub fn discover_in<P: AsRef<Path>>(root: P) -> io::Result<(PathBuf, OsRelease)> {
+        let (path, file) = open_in(root.as_ref())?;
+        Ok((path, read(file)?))
+    }
+
+    /// Attempt to parse the initrd-release file of the running initrd.
+    pub fn initrd_release() -> io::Result<OsRelease> {
+        Self::initrd_release_in("/")
+    }
+
+    /// Attempt to parse the initrd-release file of an initrd whose root directory is
+    /// `root`, such as an unpacked initramfs.
+    pub fn initrd_release_in<P: AsRef<Path>>(root: P) -> io::Result<OsRelease> {
+        Self::from_file_in(root, INITRD_RELEASE_PATH)
+    }
+
+    /// Attempt to parse the file at `path` on the system whose root directory is `root`.
+    ///
+    /// Unlike `OsRelease::from_file` with a path that includes the `root` prefix,
+    /// symlinks are resolved within `root`, so that a guest's
+    /// `/etc/os-release -> /usr/lib/os-release` does not lead to the host's file.
+    pub fn from_file_in<P: AsRef<Path>, Q: AsRef<Path>>(root: P, path: Q) -> io::Result<OsRelease> {
+        read(root::open(root, path)?)
     }
 
     /// Attempt to parse any `/etc/os-release`-like file.
-    pub fn from_file(path: &str) -> std::io::Result<OsRelease> {
-        let file = std::io::BufReader::new(std::fs::File::open(path)?);
-        Ok(OsRelease::from_iter(file.lines().flat_map(|line| line)))
+    ///
+    /// Malformed lines are skipped, but a file with an assignment that is not valid
+    /// UTF-8 fails with `io::ErrorKind::InvalidData`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
+        read(File::open(path)?)
+    }
+
+    /// Parse os-release data from `reader`, skipping malformed lines.
+    ///
+    /// Lines that are not valid UTF-8 are not skipped, but reported as
+    /// `ParseErrorKind::InvalidUtf8`, unless they are comments. `OsRelease::from_bytes_lossy` replaces invalid
+    /// sequences instead.
+    pub fn from_reader<R: Read>(reader: R) -> Result<OsRelease, Error> {
+        parse_reader(reader, Parser::lenient())
+    }
+
+    /// Parse os-release data held in memory, skipping malformed lines.
+    ///
+    /// As with `OsRelease::from_reader`, invalid UTF-8 is reported rather than skipped.
+    pub fn from_bytes(bytes: &[u8]) -> Result<OsRelease, Error> {
+        parse_bytes(bytes, Parser::lenient()).map_err(Error::Parse)
+    }
+
+    /// Parse os-release data held in memory, skipping malformed lines and replacing
+    /// invalid UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn from_bytes_lossy(bytes: &[u8]) -> OsRelease {
+        parse_bytes(bytes, Parser::lossy()).unwrap_or_default()
+    }
+
+    /// Parse any `/etc/os-release`-like file, rejecting it if any line is malformed.
+    ///
+    /// Every malformed line is reported, rather than only the first one.
+    pub fn from_file_strict<P: AsRef<Path>>(path: P) -> Result<OsRelease, Error> {
+        Self::from_reader_strict(File::open(path)?)
+    }
+
+    /// Parse os-release data from `reader`, rejecting it if any line is malformed.
+    pub fn from_reader_strict<R: Read>(reader: R) -> Result<OsRelease, Error> {
+        parse_reader(reader, Parser::strict())
+    }
+
+    /// Parse os-release data held in memory, rejecting it if any line is malformed.
+    pub fn from_bytes_strict(bytes: &[u8]) -> Result<OsRelease, Error> {
+        parse_bytes(bytes, Parser::strict()).map_err(Error::Parse)
+    }
+
+    /// The identifier of this OS followed by those in `id_like`, in the order that
+    /// os-release(5) recommends checking them.
+    ///
+    /// An absent `ID` is reported as its default of `linux`.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        let id = self.id.as_deref().unwrap_or("linux");
+        std::iter::once(id).chain(self.id_like.iter().map(String::as_str))
+    }
+
+    /// Whether this OS is `id`, or is derived from it.
+    ///
+    /// **IE:** `is_like("debian")` holds for Debian, Ubuntu and their derivatives.
+    pub fn is_like(&self, id: &str) -> bool {
+        self.ids().any(|candidate| candidate == id)
+    }
+
+    /// The first of `candidates` that this OS is or derives from, checking `id` before
+    /// `id_like` and `id_like` in its given order.
+    ///
+    /// This is the lookup an installer needs to pick the closest supported distribution.
+    pub fn closest_like<'a>(&self, candidates: &[&'a str]) -> Option<&'a str> {
+        self.ids()
+            .find_map(|id| candidates.iter().find(|&&candidate| candidate == id))
+            .copied()
+    }
+
+    /// The value of `key`, whether it is modeled by a field or kept in `extra`.
+    ///
+    /// `ID_LIKE` is returned space-separated, as it appears in the file.
+    pub fn get(&self, key: &str) -> Option<Cow<'_, str>> {
+        match key {
+            "ID_LIKE" if self.id_like.is_empty() => None,
+            "ID_LIKE" => Some(Cow::Owned(self.id_like.join(" "))),
+            key => match self.field(key) {
+                Some(field) => field.as_deref().map(Cow::Borrowed),
+                None => self
+                    .extra
+                    .get(key)
+                    .map(|value| Cow::Borrowed(value.as_str())),
+            },
+        }
+    }
+
+    /// Every key that has a value, with the modeled keys in the order of `KEYS`
+    /// followed by the keys of `extra`.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, Cow<'_, str>)> {
+        let known = KEYS
+            .iter()
+            .filter_map(move |&key| Some((key, self.get(key)?)));
+        let extra = self
+            .extra
+            .iter()
+            .map(|(key, value)| (key.as_str(), Cow::Borrowed(value.as_str())));
+        known.chain(extra)
+    }
+
+    /// Write the data out in the os-release format, as `Display` does.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` before writing anything if a value
+    /// contains a line break, since it could not be read back unchanged.
+    pub fn write_to<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        if let Some((key, _)) = self
+            .entries()
+            .find(|(_, value)| !parser::is_valid_value(value))
+        {
+            let why = ParseErrorKind::InvalidValue(key.to_owned()).to_string();
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, why));
+        }
+
+        write!(writer, "{}", self)
+    }
+
+    /// Assign `value` to the field for `key`, or to `extra` if the key is not modeled.
+    pub(crate) fn set(&mut self, key: &str, value: String) {
+        if key == "ID_LIKE" {
+            self.id_like = value.split_whitespace().map(String::from).collect();
+        } else if let Some(field) = self.field_mut(key) {
+            *field = Some(value);
+        } else {
+            self.extra.insert(key.to_owned(), value);
+        }
+    }
+
+    with_keys!(field());
+    with_keys!(field_mut(String));
+}
+
+/// Leniently parse the contents of `reader`, as `OsRelease::from_file` does.
+pub(crate) fn read<R: Read>(reader: R) -> io::Result<OsRelease> {
+    Ok(OsRelease::from_reader(reader)?)
+}
+
+fn parse_reader<R: Read>(reader: R, mut parser: Parser) -> Result<OsRelease, Error> {
+    for line in BufReader::new(reader).split(b'\n') {
+        parser.push_bytes(&line?);
+    }
+
+    parser.finish().map_err(Error::Parse)
+}
+
+fn parse_bytes(bytes: &[u8], mut parser: Parser) -> Result<OsRelease, Vec<ParseError>> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    for line in bytes.split(|&byte| byte == b'\n') {
+        parser.push_bytes(line);
+    }
+
+    parser.finish()
+}
+
+/// The os-release files of the system whose root directory is `root`, in the order
+/// they are consulted, each as its path within `root` and with the `root` prefix.
+pub(crate) fn candidates(root: &Path) -> impl Iterator<Item = (&'static str, PathBuf)> + '_ {
+    OS_RELEASE_PATHS
+        .into_iter()
+        .map(move |path| (path, root.join(path.trim_start_matches('/'))))
+}
+
+/// Open the first of `OS_RELEASE_PATHS` that exists beneath `root`.
+///
+/// Symlinks are resolved within `root`. Only a missing file moves the search on to
+/// the next location; any other error is returned as is.
+fn open_in(root: &Path) -> io::Result<(PathBuf, File)> {
+    let mut not_found = None;
+    for (path, prefixed) in candidates(root) {
+        match root::open(root, path) {
+            Ok(file) => return Ok((prefixed, file)),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => not_found = Some(why),
+            Err(why) => return Err(why),
+        }
+    }
+
+    Err(not_found.expect("OS_RELEASE_PATHS is not empty"))
+}
+
+/// Writes the data back out in the os-release format, one `KEY=value` line per entry.
+///
+/// Values are quoted and escaped as needed, so that parsing the output yields the
+/// same `OsRelease`. The exception is values containing line breaks, which are
+/// written as they are and so do not survive the round trip; `OsRelease::write_to`
+/// rejects them instead.
+impl fmt::Display for OsRelease {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in self.entries() {
+            writeln!(f, "{}={}", key, parser::quote(&value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Leniently parses os-release data, as `OsRelease::from_bytes` does, which cannot
+/// fail on a `str`.
+impl FromStr for OsRelease {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::lenient();
+        for line in text.strip_suffix('\n').unwrap_or(text).split('\n') {
+            parser.push_str(line);
+        }
+
+        Ok(parser.finish().unwrap_or_default())
     }
 }
 
 impl FromIterator<String> for OsRelease {
     fn from_iter<I: IntoIterator<Item = String>>(lines: I) -> Self {
-        let mut os_release = Self::default();
-
+        let mut parser = Parser::lenient();
         for line in lines {
-            parse_os_release_line!(&line, {
-            regex::Regex::new(r#"^NAME="?([^"]+)"?$"#).unwrap() => os_release.name,
-            regex::Regex::new(r#"^VERSION="?([^"]+)"?$"#).unwrap() => os_release.version,
-            regex::Regex::new(r#"^ID="?([^"]+)"?$"#).unwrap() => os_release.id,
-            regex::Regex::new(r#"^ID_LIKE="?([^"]+)"?$"#).unwrap() => os_release.id_like,
-            regex::Regex::new(r#"^PRETTY_NAME="?([^"]+)"?$"#).unwrap() => os_release.pretty_name,
-            regex::Regex::new(r#"^VERSION_ID="?([^"]+)"?$"#).unwrap() => os_release.version_id,
-            regex::Regex::new(r#"^HOME_URL="?([^"]+)"?$"#).unwrap() => os_release.home_url,
-            regex::Regex::new(r#"^SUPPORT_URL="?([^"]+)"?$"#).unwrap() => os_release.support_url,
-            regex::Regex::new(r#"^BUG_REPORT_URL="?([^"]+)"?$"#).unwrap() => os_release.bug_report_url,
-            regex::Regex::new(r#"^PRIVACY_POLICY_URL="?([^"]+)"?$"#).unwrap() => os_release.privacy_policy_url,
-            regex::Regex::new(r#"^VERSION_CODENAME="?([^"]+)"?$"#).unwrap() => os_release.version_codename
-            });
-            let re = regex::Regex::new(r#"(\w+)="?([^"]+)"?"#).unwrap();
-            if let Some(cap) = re.captures(&line) {
-                os_release
-                    .extra
-                    .insert(cap[1].to_owned().to_string(), String::from(&cap[2]));
+            parser.push_str(&line);
+        }
+
+        parser.finish().unwrap_or_default()
+    }
+}
+
+/// Builds an `OsRelease` one line at a time.
+///
+/// A lenient parser skips malformed lines, while a strict one records a `ParseError`
+/// for each of them. Either records lines that are not valid UTF-8, unless it is lossy
+/// or the line is a comment that a lenient parser skips anyway.
+struct Parser {
+    os_release: OsRelease,
+    line: usize,
+    /// Line on which each key was first assigned, only tracked when strict.
+    seen: Option<HashMap<String, usize>>,
+    /// Whether invalid UTF-8 is replaced rather than recorded.
+    lossy: bool,
+    errors: Vec<ParseError>,
+}
+
+impl Parser {
+    fn lenient() -> Self {
+        Parser {
+            os_release: OsRelease::default(),
+            line: 0,
+            seen: None,
+            lossy: false,
+            errors: Vec::new(),
+        }
+    }
+
+    fn lossy() -> Self {
+        Parser {
+            lossy: true,
+            ..Parser::lenient()
+        }
+    }
+
+    fn strict() -> Self {
+        Parser {
+            seen: Some(HashMap::new()),
+            ..Parser::lenient()
+        }
+    }
+
+    fn push_bytes(&mut self, line: &[u8]) {
+        if self.seen.is_none() && parser::is_ignored(line) {
+            self.line += 1;
+            return;
+        }
+
+        match std::str::from_utf8(line) {
+            Ok(line) => self.push_str(line),
+            Err(_) if self.lossy => self.push_str(&String::from_utf8_lossy(line)),
+            Err(why) => {
+                self.line += 1;
+                self.errors.push(ParseError {
+                    line: self.line,
+                    kind: ParseErrorKind::InvalidUtf8 {
+                        offset: why.valid_up_to(),
+                    },
+                });
+            }
+        }
+    }
+
+    fn push_str(&mut self, line: &str) {
+        self.line += 1;
+
+        let (key, value) = match parser::parse_line(line) {
+            Ok(Line::Assignment { key, value, .. }) => (key, value),
+            Ok(Line::Blank | Line::Comment) => return,
+            Err(kind) => return self.error(kind),
+        };
+
+        if let Some(seen) = self.seen.as_mut() {
+            match seen.get(key) {
+                Some(&first) => {
+                    let kind = ParseErrorKind::DuplicateKey {
+                        key: key.to_owned(),
+                        first,
+                    };
+                    self.errors.push(ParseError {
+                        line: self.line,
+                        kind,
+                    });
+                }
+                None => {
+                    seen.insert(key.to_owned(), self.line);
+                }
             }
         }
-        os_release
+
+        self.os_release.set(key, value.into_owned());
+    }
+
+    fn error(&mut self, kind: ParseErrorKind) {
+        if self.seen.is_some() {
+            self.errors.push(ParseError {
+                line: self.line,
+                kind,
+            });
+        }
+    }
+
+    fn finish(self) -> Result<OsRelease, Vec<ParseError>> {
+        if self.errors.is_empty() {
+            Ok(self.os_release)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// Serialization of an `OsRelease` as a flat map of its os-release keys, such as
+/// `{"ID": "fedora", "ID_LIKE": "rhel fedora"}`, with `extra` merged in.
+///
+/// Use it with `#[serde(with = "distro::os_release::keys")]` on a field, or call
+/// `serialize` and `deserialize` directly with a serializer or deserializer.
+#[cfg(feature = "serde")]
+pub mod keys {
+    use super::OsRelease;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    /// Serialize `os_release` as a map of the keys that have values.
+    pub fn serialize<S: Serializer>(
+        os_release: &OsRelease,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, value) in os_release.entries() {
+            map.serialize_entry(key, &value)?;
+        }
+        map.end()
+    }
+
+    /// Deserialize an `OsRelease` from a map of keys to string values.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OsRelease, D::Error> {
+        let mut os_release = OsRelease::default();
+        for (key, value) in BTreeMap::<String, String>::deserialize(deserializer)? {
+            os_release.set(&key, value);
+        }
+        Ok(os_release)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::strategy::Strategy;
     const EXAMPLE: &str = r#"NAME="Pop!_OS"
 VERSION="18.04 LTS"
 ID=ubuntu
@@ -124,23 +671,518 @@ ANOTHER_KEY="#;
         assert_eq!(
             os_release,
             OsRelease {
-                name: "Pop!_OS".into(),
-                version: "18.04 LTS".into(),
-                id: "ubuntu".into(),
-                id_like: "debian".into(),
-                pretty_name: "Pop!_OS 18.04 LTS".into(),
-                version_id: "18.04".into(),
-                home_url: "https://system76.com/pop".into(),
-                support_url: "http://support.system76.com".into(),
-                bug_report_url: "https://github.com/pop-os/pop/issues".into(),
-                privacy_policy_url: "https://system76.com/privacy".into(),
-                version_codename: "bionic".into(),
+                name: Some("Pop!_OS".into()),
+                version: Some("18.04 LTS".into()),
+                id: Some("ubuntu".into()),
+                id_like: vec!["debian".into()],
+                pretty_name: Some("Pop!_OS 18.04 LTS".into()),
+                version_id: Some("18.04".into()),
+                home_url: Some("https://system76.com/pop".into()),
+                support_url: Some("http://support.system76.com".into()),
+                bug_report_url: Some("https://github.com/pop-os/pop/issues".into()),
+                privacy_policy_url: Some("https://system76.com/privacy".into()),
+                version_codename: Some("bionic".into()),
                 extra: {
                     let mut map = std::collections::BTreeMap::new();
                     map.insert("EXTRA_KEY".to_owned(), "thing".to_owned());
+                    map.insert("ANOTHER_KEY".to_owned(), String::new());
                     map
-                }
+                },
+                ..OsRelease::default()
             }
         )
     }
+
+    #[test]
+    fn fedora_rawhide() {
+        let os_release = parse(include_str!("../fedora-rawhide-os-release"));
+        let some = |value: &str| Some(value.to_owned());
+
+        assert_eq!(os_release.id, some("fedora"));
+        assert_eq!(os_release.version_id, some("38"));
+        assert_eq!(os_release.version_codename, some(""));
+        assert_eq!(os_release.platform_id, some("platform:f38"));
+        assert_eq!(os_release.ansi_color, some("0;38;2;60;110;180"));
+        assert_eq!(os_release.logo, some("fedora-logo-icon"));
+        assert_eq!(os_release.cpe_name, some("cpe:/o:fedoraproject:fedora:38"));
+        assert_eq!(os_release.default_hostname, some("fedora"));
+        assert_eq!(os_release.variant, some("Workstation Edition"));
+        assert_eq!(os_release.variant_id, some("workstation"));
+        assert!(os_release.id_like.is_empty());
+        assert_eq!(os_release.support_end, None);
+        assert_eq!(
+            os_release.extra.keys().collect::<Vec<_>>(),
+            [
+                "REDHAT_BUGZILLA_PRODUCT",
+                "REDHAT_BUGZILLA_PRODUCT_VERSION",
+                "REDHAT_SUPPORT_PRODUCT",
+                "REDHAT_SUPPORT_PRODUCT_VERSION",
+            ]
+        );
+    }
+
+    #[test]
+    fn id_like() {
+        let rocky = parse("ID=rocky\nID_LIKE=\"rhel centos  fedora\"");
+        assert_eq!(rocky.id_like, ["rhel", "centos", "fedora"]);
+        assert_eq!(
+            rocky.ids().collect::<Vec<_>>(),
+            ["rocky", "rhel", "centos", "fedora"]
+        );
+        assert!(rocky.is_like("rocky"));
+        assert!(rocky.is_like("rhel"));
+        assert!(!rocky.is_like("debian"));
+
+        assert_eq!(rocky.closest_like(&["fedora", "rhel"]), Some("rhel"));
+        assert_eq!(rocky.closest_like(&["fedora", "rocky"]), Some("rocky"));
+        assert_eq!(rocky.closest_like(&["debian", "arch"]), None);
+    }
+
+    #[test]
+    fn id_like_defaults() {
+        let empty = parse("ID_LIKE=");
+        assert!(empty.id_like.is_empty());
+        assert_eq!(empty.ids().collect::<Vec<_>>(), ["linux"]);
+        assert!(empty.is_like("linux"));
+    }
+
+    #[test]
+    fn display() {
+        let os_release = parse(EXAMPLE);
+        let expected = r#"NAME="Pop!_OS"
+ID=ubuntu
+ID_LIKE=debian
+PRETTY_NAME="Pop!_OS 18.04 LTS"
+VERSION="18.04 LTS"
+VERSION_ID=18.04
+VERSION_CODENAME=bionic
+HOME_URL="https://system76.com/pop"
+SUPPORT_URL="http://support.system76.com"
+BUG_REPORT_URL="https://github.com/pop-os/pop/issues"
+PRIVACY_POLICY_URL="https://system76.com/privacy"
+ANOTHER_KEY=""
+EXTRA_KEY=thing
+"#;
+        assert_eq!(os_release.to_string(), expected);
+    }
+
+    #[test]
+    fn display_escapes() {
+        let os_release = OsRelease {
+            name: Some(r#"Foo "Bar" $HOME `id` \"#.into()),
+            id_like: vec!["rhel".into(), "fedora".into()],
+            ..OsRelease::default()
+        };
+        let text = os_release.to_string();
+        assert_eq!(
+            text,
+            concat!(
+                r#"NAME="Foo \"Bar\" \$HOME \`id\` \\""#,
+                "\n",
+                r#"ID_LIKE="rhel fedora""#,
+                "\n",
+            )
+        );
+        assert_eq!(parse(&text), os_release);
+    }
+
+    #[test]
+    fn line_breaks() {
+        let os_release = OsRelease {
+            name: Some("a\nID=evil\n".into()),
+            ..OsRelease::default()
+        };
+
+        // `Display` cannot represent the line break, and the value is lost.
+        let text = os_release.to_string();
+        assert_eq!(text, "NAME=\"a\nID=evil\n\"\n");
+        assert_eq!(parse(&text).id.as_deref(), Some("evil"));
+        assert_ne!(parse(&text), os_release);
+
+        let mut written = Vec::new();
+        let why = os_release.write_to(&mut written).unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(why.to_string(), "the value of `NAME` contains a line break");
+        assert!(written.is_empty());
+
+        let carriage_return = OsRelease {
+            extra: [("KEY".into(), "a\rb".into())].into_iter().collect(),
+            ..OsRelease::default()
+        };
+        assert!(carriage_return.write_to(&mut written).is_err());
+
+        let fedora = parse(EXAMPLE);
+        fedora.write_to(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), fedora.to_string());
+    }
+
+    #[test]
+    fn get() {
+        let os_release = parse(EXAMPLE);
+        assert_eq!(os_release.get("ID").as_deref(), Some("ubuntu"));
+        assert_eq!(os_release.get("ID_LIKE").as_deref(), Some("debian"));
+        assert_eq!(os_release.get("EXTRA_KEY").as_deref(), Some("thing"));
+        assert_eq!(os_release.get("LOGO"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_field_names() {
+        let os_release = parse(EXAMPLE);
+        let json = serde_json::to_value(&os_release).unwrap();
+        assert_eq!(json["id"], "ubuntu");
+        assert_eq!(json["id_like"], serde_json::json!(["debian"]));
+        assert_eq!(json["logo"], serde_json::Value::Null);
+        assert_eq!(json["extra"]["EXTRA_KEY"], "thing");
+        assert_eq!(
+            serde_json::from_value::<OsRelease>(json).unwrap(),
+            os_release
+        );
+
+        let partial: OsRelease = serde_json::from_str(r#"{"id": "fedora"}"#).unwrap();
+        assert_eq!(partial.id.as_deref(), Some("fedora"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_key_names() {
+        let os_release = parse(EXAMPLE);
+        let json = keys::serialize(&os_release, serde_json::value::Serializer).unwrap();
+        assert_eq!(json["ID"], "ubuntu");
+        assert_eq!(json["ID_LIKE"], "debian");
+        assert_eq!(json["EXTRA_KEY"], "thing");
+        assert!(json.get("LOGO").is_none());
+        assert_eq!(keys::deserialize(json).unwrap(), os_release);
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Host {
+            #[serde(with = "keys")]
+            os_release: OsRelease,
+        }
+
+        let host: Host = serde_json::from_str(r#"{"os_release": {"ID": "arch"}}"#).unwrap();
+        assert_eq!(host.os_release.id.as_deref(), Some("arch"));
+        let json = serde_json::to_string(&host).unwrap();
+        assert_eq!(json, r#"{"os_release":{"ID":"arch"}}"#);
+    }
+
+    /// Values without line breaks, which `Display` cannot represent; see `line_breaks`.
+    fn value_strategy() -> impl Strategy<Value = String> {
+        "[^\n\r]*"
+    }
+
+    fn os_release_strategy() -> impl Strategy<Value = OsRelease> {
+        let fields = proptest::collection::vec(proptest::option::of(value_strategy()), KEYS.len());
+        let id_like = proptest::collection::vec("[a-z0-9._-]{1,10}", 0..4);
+        let extra = proptest::collection::btree_map(
+            "[A-Z_][A-Z0-9_]{0,12}".prop_filter("modeled key", |key| !KEYS.contains(&key.as_str())),
+            value_strategy(),
+            0..4,
+        );
+
+        (fields, id_like, extra).prop_map(|(fields, id_like, extra)| {
+            let mut os_release = OsRelease {
+                extra,
+                ..OsRelease::default()
+            };
+            for (key, value) in KEYS.iter().zip(fields) {
+                if let Some(value) = value.filter(|_| *key != "ID_LIKE") {
+                    os_release.set(key, value);
+                }
+            }
+            os_release.id_like = id_like;
+            os_release
+        })
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn display_round_trips(os_release in os_release_strategy()) {
+            let text = os_release.to_string();
+            let mut written = Vec::new();
+            os_release.write_to(&mut written).unwrap();
+            proptest::prop_assert_eq!(&written, text.as_bytes());
+            proptest::prop_assert_eq!(&parse(&text), &os_release);
+            proptest::prop_assert_eq!(&strict(text.as_bytes()).unwrap(), &os_release);
+        }
+    }
+
+    fn parse(input: &str) -> OsRelease {
+        OsRelease::from_iter(input.lines().map(|x| x.to_owned()))
+    }
+
+    fn value(input: &str) -> Option<String> {
+        parse(input).extra.remove("KEY")
+    }
+
+    #[test]
+    fn unquoted_values() {
+        assert_eq!(value("KEY=fedora"), Some("fedora".into()));
+        assert_eq!(value("KEY=a\\ b"), Some("a b".into()));
+        assert_eq!(value("KEY=a#b"), Some("a#b".into()));
+        assert_eq!(value("KEY=18.04 LTS"), Some("18.04 LTS".into()));
+    }
+
+    #[test]
+    fn double_quoted_values() {
+        assert_eq!(value(r#"KEY="Foo \"Bar\"""#), Some(r#"Foo "Bar""#.into()));
+        assert_eq!(value(r#"KEY="a\\b""#), Some(r"a\b".into()));
+        assert_eq!(value(r#"KEY="\$HOME \`id\`""#), Some("$HOME `id`".into()));
+        assert_eq!(value(r#"KEY="\n""#), Some(r"\n".into()));
+        assert_eq!(value(r#"KEY="it's""#), Some("it's".into()));
+        assert_eq!(
+            value(r##"KEY="# not a comment""##),
+            Some("# not a comment".into())
+        );
+    }
+
+    #[test]
+    fn single_quoted_values() {
+        assert_eq!(value("KEY='Foo Bar'"), Some("Foo Bar".into()));
+        assert_eq!(value(r#"KEY='a\b "c" $d'"#), Some(r#"a\b "c" $d"#.into()));
+    }
+
+    #[test]
+    fn empty_values() {
+        assert_eq!(value("KEY="), Some(String::new()));
+        assert_eq!(value(r#"KEY="""#), Some(String::new()));
+        assert_eq!(value("KEY=''"), Some(String::new()));
+        assert_eq!(value("KEY=  "), Some(String::new()));
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        assert_eq!(value("KEY=value   "), Some("value".into()));
+        assert_eq!(value("KEY=\"value\"\t"), Some("value".into()));
+        assert_eq!(value("  KEY=value"), Some("value".into()));
+        assert_eq!(value("KEY=value # comment"), Some("value".into()));
+        assert_eq!(value("KEY=\"value\" # comment"), Some("value".into()));
+        assert_eq!(value("# KEY=value"), None);
+        assert_eq!(value("   # KEY=value"), None);
+    }
+
+    #[test]
+    fn malformed_lines() {
+        assert_eq!(value(r#"KEY="unterminated"#), None);
+        assert_eq!(value("KEY='unterminated"), None);
+        assert_eq!(value("KEY value"), None);
+        assert_eq!(value("KEY =value"), None);
+    }
+
+    #[test]
+    fn later_assignments_win() {
+        assert_eq!(value("KEY=first\nKEY=second"), Some("second".into()));
+        assert_eq!(
+            parse("ID=first\nID=\"second\"").id.as_deref(),
+            Some("second")
+        );
+    }
+
+    fn strict(input: &[u8]) -> Result<OsRelease, Error> {
+        OsRelease::from_reader_strict(input)
+    }
+
+    fn errors(input: &[u8]) -> Vec<ParseError> {
+        match strict(input) {
+            Err(Error::Parse(errors)) => errors,
+            other => panic!("expected parse errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn strict_accepts_valid_input() {
+        let expected = parse(EXAMPLE);
+        assert_eq!(strict(EXAMPLE.as_bytes()).unwrap(), expected);
+        let fedora = include_bytes!("../fedora-rawhide-os-release");
+        assert_eq!(strict(fedora).unwrap().id.as_deref(), Some("fedora"));
+    }
+
+    #[test]
+    fn strict_reports_each_problem() {
+        let input = b"ID=fedora\n# comment\n\nNOT AN ASSIGNMENT\nBAD-KEY=1\n1KEY=2\n\
+            NAME=\"Fedora\nID=again\nVERSION=\xff\n";
+        let e = |line, kind| ParseError { line, kind };
+        assert_eq!(
+            errors(input),
+            vec![
+                e(4, ParseErrorKind::MissingAssignment),
+                e(5, ParseErrorKind::InvalidKey("BAD-KEY".into())),
+                e(6, ParseErrorKind::InvalidKey("1KEY".into())),
+                e(7, ParseErrorKind::UnterminatedQuote),
+                e(
+                    8,
+                    ParseErrorKind::DuplicateKey {
+                        key: "ID".into(),
+                        first: 1
+                    }
+                ),
+                e(9, ParseErrorKind::InvalidUtf8 { offset: 8 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn bytes_readers_and_strs() {
+        let fedora = include_str!("../fedora-rawhide-os-release");
+        let expected = parse(fedora);
+        assert_eq!(OsRelease::from_bytes(fedora.as_bytes()).unwrap(), expected);
+        assert_eq!(OsRelease::from_reader(fedora.as_bytes()).unwrap(), expected);
+        assert_eq!(fedora.parse::<OsRelease>().unwrap(), expected);
+        assert_eq!(
+            OsRelease::from_bytes_strict(fedora.as_bytes()).unwrap(),
+            expected
+        );
+
+        // Malformed lines are skipped, as with `from_file`.
+        let lenient = OsRelease::from_bytes(b"ID=a\nNOT AN ASSIGNMENT\nNAME=\"b").unwrap();
+        assert_eq!(lenient.id.as_deref(), Some("a"));
+        assert_eq!(lenient.name, None);
+    }
+
+    #[test]
+    fn invalid_utf8_is_not_skipped() {
+        let input = b"ID=fedora\nNAME=F\xe9dora\n";
+        let invalid = vec![ParseError {
+            line: 2,
+            kind: ParseErrorKind::InvalidUtf8 { offset: 6 },
+        }];
+
+        match OsRelease::from_bytes(input) {
+            Err(Error::Parse(errors)) => assert_eq!(errors, invalid),
+            other => panic!("expected parse errors, got {:?}", other),
+        }
+        match OsRelease::from_reader(&input[..]) {
+            Err(Error::Parse(errors)) => assert_eq!(errors, invalid),
+            other => panic!("expected parse errors, got {:?}", other),
+        }
+
+        let lossy = OsRelease::from_bytes_lossy(input);
+        assert_eq!(lossy.id.as_deref(), Some("fedora"));
+        assert_eq!(lossy.name.as_deref(), Some("F\u{fffd}dora"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("os-release"), input).unwrap();
+        let why = OsRelease::from_file(dir.path().join("os-release")).unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            why.to_string(),
+            "malformed os-release: line 2: invalid UTF-8 at byte 6"
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_in_comments_is_skipped() {
+        let input = b"ID=fedora\n# caf\xe9\n  #\xff\n\n";
+        assert_eq!(
+            OsRelease::from_bytes(input).unwrap().id.as_deref(),
+            Some("fedora")
+        );
+        assert!(OsRelease::from_reader(&input[..]).is_ok());
+        assert!(crate::OsReleaseRef::from_bytes(input).is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("os-release"), input).unwrap();
+        assert!(OsRelease::from_file(dir.path().join("os-release")).is_ok());
+
+        // Strict parsing still rejects the file.
+        assert!(OsRelease::from_bytes_strict(input).is_err());
+    }
+
+    #[test]
+    fn strict_error_message() {
+        let why = strict(b"ID=a\nID=b\n").unwrap_err();
+        assert_eq!(
+            why.to_string(),
+            "malformed os-release: line 2: duplicate key `ID`, first assigned on line 1"
+        );
+    }
+
+    #[test]
+    fn lenient_skips_problems() {
+        let os_release = OsRelease::from_file_strict("/nonexistent/os-release");
+        assert!(matches!(os_release, Err(Error::Io(_))));
+
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "/etc/os-release",
+            "ID=\"a\nBAD-KEY=1\nNAME=Name\n",
+        );
+        let os_release = OsRelease::from_file(root.path().join("etc/os-release")).unwrap();
+        assert_eq!(os_release.id, None);
+        assert_eq!(os_release.name.as_deref(), Some("Name"));
+        assert!(os_release.extra.is_empty());
+    }
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path.trim_start_matches('/'));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn discover_prefers_etc() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "/etc/os-release", "ID=etc\n");
+        write(root.path(), "/usr/lib/os-release", "ID=usr\n");
+
+        let (path, os_release) = OsRelease::discover_in(root.path()).unwrap();
+        assert_eq!(path, root.path().join("etc/os-release"));
+        assert_eq!(os_release.id.as_deref(), Some("etc"));
+    }
+
+    #[test]
+    fn discover_falls_back_to_usr_lib() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "/usr/lib/os-release", "ID=usr\n");
+
+        let (path, os_release) = OsRelease::discover_in(root.path()).unwrap();
+        assert_eq!(path, root.path().join("usr/lib/os-release"));
+        assert_eq!(os_release.id.as_deref(), Some("usr"));
+    }
+
+    #[test]
+    fn discover_missing() {
+        let root = tempfile::tempdir().unwrap();
+        let why = OsRelease::discover_in(root.path()).unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn initrd_release_in() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "/usr/lib/os-release", "ID=host\n");
+        let why = OsRelease::initrd_release_in(root.path()).unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::NotFound);
+
+        write(
+            root.path(),
+            INITRD_RELEASE_PATH,
+            "ID=fedora\nVARIANT_ID=initrd\n",
+        );
+        let initrd = OsRelease::initrd_release_in(root.path()).unwrap();
+        assert_eq!(initrd.variant_id.as_deref(), Some("initrd"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn discover_resolves_links_within_root() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "/usr/lib/os-release", "ID=guest\n");
+        std::fs::create_dir(root.path().join("etc")).unwrap();
+        // Followed naively, this would read the host's file.
+        std::os::unix::fs::symlink("/usr/lib/os-release", root.path().join("etc/os-release"))
+            .unwrap();
+
+        let (path, os_release) = OsRelease::discover_in(root.path()).unwrap();
+        assert_eq!(path, root.path().join("etc/os-release"));
+        assert_eq!(os_release.id.as_deref(), Some("guest"));
+        assert_eq!(
+            OsRelease::from_file_in(root.path(), "/etc/os-release")
+                .unwrap()
+                .id
+                .as_deref(),
+            Some("guest")
+        );
+    }
 }
@@ -0,0 +1,267 @@
+use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::os_release::{field_mut, with_keys};
+use crate::parser::{self, Line};
+use crate::OsRelease;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// A view of os-release data that borrows from the text it was parsed from.
+///
+/// Values are only allocated when they have to be unescaped, which makes this cheaper
+/// than `OsRelease` when many files are parsed and few of their values are kept.
+/// `OsRelease::from` converts it into the owned form, moving the values that were
+/// already allocated.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OsReleaseRef<'a> {
+    /// See [`OsRelease::architecture`].
+    pub architecture: Option<Cow<'a, str>>,
+    /// See [`OsRelease::ansi_color`].
+    pub ansi_color: Option<Cow<'a, str>>,
+    /// See [`OsRelease::bug_report_url`].
+    pub bug_report_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::build_id`].
+    pub build_id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::confext_level`].
+    pub confext_level: Option<Cow<'a, str>>,
+    /// See [`OsRelease::confext_scope`].
+    pub confext_scope: Option<Cow<'a, str>>,
+    /// See [`OsRelease::cpe_name`].
+    pub cpe_name: Option<Cow<'a, str>>,
+    /// See [`OsRelease::default_hostname`].
+    pub default_hostname: Option<Cow<'a, str>>,
+    /// See [`OsRelease::documentation_url`].
+    pub documentation_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::experiment`].
+    pub experiment: Option<Cow<'a, str>>,
+    /// See [`OsRelease::experiment_url`].
+    pub experiment_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::home_url`].
+    pub home_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::id_like`].
+    pub id_like: Vec<Cow<'a, str>>,
+    /// See [`OsRelease::id`].
+    pub id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::image_id`].
+    pub image_id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::image_version`].
+    pub image_version: Option<Cow<'a, str>>,
+    /// See [`OsRelease::logo`].
+    pub logo: Option<Cow<'a, str>>,
+    /// See [`OsRelease::name`].
+    pub name: Option<Cow<'a, str>>,
+    /// See [`OsRelease::platform_id`].
+    pub platform_id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::portable_prefixes`].
+    pub portable_prefixes: Option<Cow<'a, str>>,
+    /// See [`OsRelease::pretty_name`].
+    pub pretty_name: Option<Cow<'a, str>>,
+    /// See [`OsRelease::privacy_policy_url`].
+    pub privacy_policy_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::release_type`].
+    pub release_type: Option<Cow<'a, str>>,
+    /// See [`OsRelease::support_end`].
+    pub support_end: Option<Cow<'a, str>>,
+    /// See [`OsRelease::support_url`].
+    pub support_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::sysext_level`].
+    pub sysext_level: Option<Cow<'a, str>>,
+    /// See [`OsRelease::sysext_scope`].
+    pub sysext_scope: Option<Cow<'a, str>>,
+    /// See [`OsRelease::variant`].
+    pub variant: Option<Cow<'a, str>>,
+    /// See [`OsRelease::variant_id`].
+    pub variant_id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::vendor_name`].
+    pub vendor_name: Option<Cow<'a, str>>,
+    /// See [`OsRelease::vendor_url`].
+    pub vendor_url: Option<Cow<'a, str>>,
+    /// See [`OsRelease::version_codename`].
+    pub version_codename: Option<Cow<'a, str>>,
+    /// See [`OsRelease::version_id`].
+    pub version_id: Option<Cow<'a, str>>,
+    /// See [`OsRelease::version`].
+    pub version: Option<Cow<'a, str>>,
+    /// Additional keys not covered by the API.
+    pub extra: BTreeMap<&'a str, Cow<'a, str>>,
+}
+
+impl<'a> OsReleaseRef<'a> {
+    /// Parse the contents of an os-release file, skipping malformed lines.
+    pub fn parse(text: &'a str) -> OsReleaseRef<'a> {
+        let mut os_release = OsReleaseRef::default();
+        for line in text.strip_suffix('\n').unwrap_or(text).split('\n') {
+            os_release.push(line);
+        }
+        os_release
+    }
+
+    /// Parse os-release data held in memory, skipping malformed lines.
+    ///
+    /// As with `OsRelease::from_bytes`, every line that is not valid UTF-8 is reported
+    /// rather than skipped.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<OsReleaseRef<'a>, Error> {
+        let mut os_release = OsReleaseRef::default();
+        let mut errors = Vec::new();
+        let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        for (n, line) in bytes.split(|&byte| byte == b'\n').enumerate() {
+            if parser::is_ignored(line) {
+                continue;
+            }
+
+            match std::str::from_utf8(line) {
+                Ok(line) => os_release.push(line),
+                Err(why) => errors.push(ParseError {
+                    line: n + 1,
+                    kind: ParseErrorKind::InvalidUtf8 {
+                        offset: why.valid_up_to(),
+                    },
+                }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(os_release)
+        } else {
+            Err(Error::Parse(errors))
+        }
+    }
+
+    /// Convert into an `OsRelease`, allocating only the values that are borrowed.
+    pub fn into_owned(self) -> OsRelease {
+        OsRelease::from(self)
+    }
+
+    fn push(&mut self, line: &'a str) {
+        if let Ok(Line::Assignment { key, value, .. }) = parser::parse_line(line) {
+            self.set(key, value);
+        }
+    }
+
+    fn set(&mut self, key: &'a str, value: Cow<'a, str>) {
+        if key == "ID_LIKE" {
+            self.id_like = match value {
+                Cow::Borrowed(value) => value.split_whitespace().map(Cow::Borrowed).collect(),
+                Cow::Owned(value) => value
+                    .split_whitespace()
+                    .map(|id| Cow::Owned(id.to_owned()))
+                    .collect(),
+            };
+        } else if let Some(field) = self.field_mut(key) {
+            *field = Some(value);
+        } else {
+            self.extra.insert(key, value);
+        }
+    }
+
+    with_keys!(field_mut(Cow<'a, str>));
+}
+
+/// Generates the conversions between `OsRelease` and `OsReleaseRef`.
+macro_rules! conversions {
+    (() [$($before:ident: $b:literal,)*] $id_like:ident: $l:literal, [$($after:ident: $a:literal,)*]) => {
+        impl From<OsReleaseRef<'_>> for OsRelease {
+            fn from(os_release: OsReleaseRef<'_>) -> Self {
+                OsRelease {
+                    $($before: os_release.$before.map(Cow::into_owned),)*
+                    $id_like: os_release
+                        .$id_like
+                        .into_iter()
+                        .map(Cow::into_owned)
+                        .collect(),
+                    $($after: os_release.$after.map(Cow::into_owned),)*
+                    extra: os_release
+                        .extra
+                        .into_iter()
+                        .map(|(key, value)| (key.to_owned(), value.into_owned()))
+                        .collect(),
+                }
+            }
+        }
+
+        impl<'a> From<&'a OsRelease> for OsReleaseRef<'a> {
+            fn from(os_release: &'a OsRelease) -> Self {
+                OsReleaseRef {
+                    $($before: os_release.$before.as_deref().map(Cow::Borrowed),)*
+                    $id_like: os_release
+                        .$id_like
+                        .iter()
+                        .map(|id| Cow::Borrowed(id.as_str()))
+                        .collect(),
+                    $($after: os_release.$after.as_deref().map(Cow::Borrowed),)*
+                    extra: os_release
+                        .extra
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), Cow::Borrowed(value.as_str())))
+                        .collect(),
+                }
+            }
+        }
+    };
+}
+
+with_keys!(conversions());
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FEDORA: &str = include_str!("../fedora-rawhide-os-release");
+
+    #[test]
+    fn matches_os_release() {
+        let expected: OsRelease = FEDORA.parse().unwrap();
+        let borrowed = OsReleaseRef::parse(FEDORA);
+        assert_eq!(OsReleaseRef::from(&expected), borrowed);
+        assert_eq!(borrowed.into_owned(), expected);
+        assert_eq!(
+            OsReleaseRef::from_bytes(FEDORA.as_bytes())
+                .unwrap()
+                .into_owned(),
+            expected
+        );
+    }
+
+    #[test]
+    fn borrows_unless_unescaping() {
+        let text = "ID=fedora\nNAME='Fedora Linux'\nVARIANT=\"Say \\\"hi\\\"\"\nID_LIKE=\"rhel centos\"\nFOO=bar\n";
+        let os_release = OsReleaseRef::parse(text);
+        assert!(matches!(os_release.id, Some(Cow::Borrowed("fedora"))));
+        assert!(matches!(
+            os_release.name,
+            Some(Cow::Borrowed("Fedora Linux"))
+        ));
+        assert!(
+            matches!(&os_release.variant, Some(Cow::Owned(variant)) if variant == "Say \"hi\"")
+        );
+        assert!(os_release
+            .id_like
+            .iter()
+            .all(|id| matches!(id, Cow::Borrowed(_))));
+        assert_eq!(os_release.id_like, ["rhel", "centos"]);
+        assert_eq!(
+            os_release.extra.get("FOO").map(|foo| foo.as_ref()),
+            Some("bar")
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        match OsReleaseRef::from_bytes(b"ID=fedora\nNAME=F\xe9dora\nVARIANT=\xff\n") {
+            Err(Error::Parse(errors)) => assert_eq!(
+                errors,
+                [
+                    ParseError {
+                        line: 2,
+                        kind: ParseErrorKind::InvalidUtf8 { offset: 6 },
+                    },
+                    ParseError {
+                        line: 3,
+                        kind: ParseErrorKind::InvalidUtf8 { offset: 8 },
+                    }
+                ]
+            ),
+            other => panic!("expected parse errors, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Tokenizer shared by everything that reads the os-release format.
+
+use crate::error::ParseErrorKind;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A single line of an os-release file, split into its parts.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Line<'a> {
+    /// An empty or whitespace-only line.
+    Blank,
+    /// A line starting with `#`.
+    Comment,
+    /// A `KEY=value` line, with the value already unquoted.
+    Assignment {
+        key: &'a str,
+        value: Cow<'a, str>,
+        /// Byte range of the value as written, quotes included, within the line.
+        span: Range<usize>,
+    },
+}
+
+/// How a value is quoted in an os-release file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quoting {
+    /// Written without quotes, as in `ID=fedora`.
+    Bare,
+    /// Enclosed in double quotes, as in `NAME="Fedora Linux"`.
+    Double,
+    /// Enclosed in single quotes, as in `NAME='Fedora Linux'`.
+    Single,
+}
+
+impl Quoting {
+    /// The quoting of a value as written, judged by its first character.
+    pub(crate) fn of(raw: &str) -> Quoting {
+        match raw.chars().next() {
+            Some('"') => Quoting::Double,
+            Some('\'') => Quoting::Single,
+            _ => Quoting::Bare,
+        }
+    }
+}
+
+/// Split a line into its key and unquoted value in a single pass.
+///
+/// The value borrows from `line` unless unquoting had to rewrite it.
+pub(crate) fn parse_line(line: &str) -> Result<Line<'_>, ParseErrorKind> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() {
+        return Ok(Line::Blank);
+    } else if trimmed.starts_with('#') {
+        return Ok(Line::Comment);
+    }
+
+    let (key, value) = trimmed
+        .split_once('=')
+        .ok_or(ParseErrorKind::MissingAssignment)?;
+    if !is_valid_key(key) {
+        return Err(ParseErrorKind::InvalidKey(key.to_owned()));
+    }
+
+    let (value, span) = unquote(value).ok_or(ParseErrorKind::UnterminatedQuote)?;
+    let offset = line.len() - trimmed.len() + key.len() + 1;
+    Ok(Line::Assignment {
+        key,
+        value,
+        span: offset + span.start..offset + span.end,
+    })
+}
+
+/// Whether `key` is a valid shell variable name.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    let mut bytes = key.bytes();
+    matches!(bytes.next(), Some(b'A'..=b'Z' | b'a'..=b'z' | b'_'))
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Whether `line` is blank or a comment, which can be told before decoding it.
+pub(crate) fn is_ignored(line: &[u8]) -> bool {
+    matches!(line.trim_ascii_start().first(), None | Some(b'#'))
+}
+
+/// Whether `value` can be written on a single line.
+pub(crate) fn is_valid_value(value: &str) -> bool {
+    !value.contains(['\n', '\r'])
+}
+
+/// Decode the right-hand side of an assignment, borrowing from `raw` when the
+/// common forms `value`, `"value"` and `'value'` need no rewriting.
+///
+/// Also returns the byte range of `raw` that the value was written in.
+fn unquote(raw: &str) -> Option<(Cow<'_, str>, Range<usize>)> {
+    let start = raw.len() - raw.trim_start().len();
+    let trimmed = raw.trim();
+    let inner = |quote: char| {
+        trimmed
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+    };
+
+    let borrowed =
+        if !trimmed.contains(|c: char| matches!(c, '"' | '\'' | '\\') || c.is_whitespace()) {
+            Some(trimmed)
+        } else if let Some(inner) = inner('"').filter(|i| !i.contains(['"', '\\', '$', '`'])) {
+            Some(inner)
+        } else {
+            inner('\'').filter(|i| !i.contains('\''))
+        };
+
+    match borrowed {
+        Some(value) => Some((Cow::Borrowed(value), start..start + trimmed.len())),
+        None => {
+            let (value, len) = unescape(&raw[start..])?;
+            Some((Cow::Owned(value), start..start + len))
+        }
+    }
+}
+
+/// Decode the right-hand side of an assignment with the shell-style rules of os-release(5).
+///
+/// Double-quoted text may escape `"`, `\`, `$` and `` ` `` with a backslash, while
+/// single-quoted text is taken literally. Outside of quotes a backslash escapes any
+/// character, and whitespace followed by a `#` starts a comment. Trailing whitespace
+/// is dropped. Returns `None` if a quote is left unterminated.
+///
+/// `raw` must not start with whitespace. Also returns the length of the text that
+/// makes up the value, without any trailing whitespace or comment.
+fn unescape(raw: &str) -> Option<(String, usize)> {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    let mut len = 0;
+    // Unquoted whitespace is only kept if more of the value follows it.
+    let mut blanks = String::new();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            blanks.push(c);
+            continue;
+        }
+
+        if !blanks.is_empty() {
+            if c == '#' {
+                break;
+            }
+            value.push_str(&blanks);
+            blanks.clear();
+        }
+
+        match c {
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        escaped @ ('"' | '\\' | '$' | '`') => value.push(escaped),
+                        other => {
+                            value.push('\\');
+                            value.push(other);
+                        }
+                    },
+                    other => value.push(other),
+                }
+            },
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    other => value.push(other),
+                }
+            },
+            '\\' => value.push(chars.next().unwrap_or('\\')),
+            other => value.push(other),
+        }
+
+        len = raw.len() - chars.as_str().len();
+    }
+
+    Some((value, len))
+}
+
+/// Quote `value` so that `unquote` reads it back unchanged.
+///
+/// Values made only of characters that are safe in a shell word are left bare,
+/// and everything else is double-quoted with the shell special characters escaped.
+pub(crate) fn quote(value: &str) -> Cow<'_, str> {
+    quote_as(value, Quoting::Bare)
+}
+
+/// Quote `value` in the style of `quoting` where that can represent it, and
+/// otherwise fall back to double quotes.
+pub(crate) fn quote_as(value: &str, quoting: Quoting) -> Cow<'_, str> {
+    let is_bare = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+    match quoting {
+        Quoting::Bare if !value.is_empty() && value.chars().all(is_bare) => {
+            return Cow::Borrowed(value);
+        }
+        Quoting::Single if !value.contains('\'') => return Cow::Owned(format!("'{}'", value)),
+        _ => (),
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assignment(line: &str) -> (&str, Cow<'_, str>) {
+        match parse_line(line) {
+            Ok(Line::Assignment { key, value, .. }) => (key, value),
+            other => panic!("expected an assignment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn line_kinds() {
+        assert_eq!(parse_line(""), Ok(Line::Blank));
+        assert_eq!(parse_line(" \t"), Ok(Line::Blank));
+        assert_eq!(parse_line("  # ID=fedora"), Ok(Line::Comment));
+        assert_eq!(parse_line("ID"), Err(ParseErrorKind::MissingAssignment));
+        assert_eq!(
+            parse_line("I D=x"),
+            Err(ParseErrorKind::InvalidKey("I D".into()))
+        );
+        assert_eq!(parse_line("ID='x"), Err(ParseErrorKind::UnterminatedQuote));
+    }
+
+    #[test]
+    fn simple_values_are_borrowed() {
+        for line in [
+            "ID=fedora",
+            "ID=\"fedora\"",
+            "ID='fedora'",
+            "ID=fedora  ",
+            "ID=\"\"",
+        ] {
+            let (key, value) = assignment(line);
+            assert_eq!(key, "ID");
+            assert!(
+                matches!(value, Cow::Borrowed(_)),
+                "{} was not borrowed",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let span = |line| match parse_line(line) {
+            Ok(Line::Assignment { span, .. }) => &line[span],
+            other => panic!("expected an assignment, got {:?}", other),
+        };
+        assert_eq!(span("ID=fedora"), "fedora");
+        assert_eq!(span("  ID= \"fedora\"  "), "\"fedora\"");
+        assert_eq!(span("NAME=\"a \\\"b\\\"\" # comment"), "\"a \\\"b\\\"\"");
+        assert_eq!(span("NAME='a'\"b\" c  # comment"), "'a'\"b\" c");
+        assert_eq!(span("ID="), "");
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("fedora"), "fedora");
+        assert_eq!(quote("18.04"), "18.04");
+        assert_eq!(quote(""), "\"\"");
+        assert_eq!(quote("Fedora Linux"), "\"Fedora Linux\"");
+        assert_eq!(quote(r#"a"b\c$d`e"#), r#""a\"b\\c\$d\`e""#);
+        for value in ["  padded ", "it's", "#hash", r"\", "tab\there"] {
+            let line = format!("KEY={}", quote(value));
+            assert_eq!(assignment(&line).1, value);
+        }
+
+        assert_eq!(quote_as("fedora", Quoting::Double), "\"fedora\"");
+        assert_eq!(quote_as("Fedora Linux", Quoting::Single), "'Fedora Linux'");
+        assert_eq!(quote_as("it's", Quoting::Single), "\"it's\"");
+        assert_eq!(quote_as("a b", Quoting::Bare), "\"a b\"");
+    }
+
+    #[test]
+    fn escaped_values_are_owned() {
+        let (_, value) = assignment(r#"NAME="Foo \"Bar\"""#);
+        assert_eq!(value, Cow::<str>::Owned(r#"Foo "Bar""#.into()));
+        let (_, value) = assignment("NAME=\"a\"'b'");
+        assert_eq!(value, "ab");
+        let (_, value) = assignment("NAME=\"a\" # comment");
+        assert_eq!(value, "a");
+    }
+}
@@ -0,0 +1,190 @@
+//! Access to the files of a system whose root directory is not `/`, such as a mounted
+//! guest filesystem or a chroot.
+//!
+//! Symlinks are resolved as they would be by that system: absolute targets are taken
+//! relative to its root instead of the host's. A link that climbs above the root
+//! with `..` is an error rather than a way out of it.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// How many symlinks may be followed while resolving a path, as with `ELOOP`.
+pub(crate) const MAX_SYMLINKS: usize = 40;
+
+/// The reasons that a path cannot be resolved within a root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymlinkError {
+    /// Too many symlinks were followed, as happens when they form a loop.
+    Loop(PathBuf),
+    /// The path, or a symlink along it, leads above the root.
+    Escape(PathBuf),
+}
+
+impl fmt::Display for SymlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymlinkError::Loop(path) => {
+                write!(f, "too many levels of symbolic links at {}", path.display())
+            }
+            SymlinkError::Escape(path) => {
+                write!(f, "{} leads outside of the root directory", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SymlinkError {}
+
+impl From<SymlinkError> for io::Error {
+    fn from(why: SymlinkError) -> Self {
+        let kind = match why {
+            SymlinkError::Loop(_) => io::ErrorKind::InvalidData,
+            SymlinkError::Escape(_) => io::ErrorKind::PermissionDenied,
+        };
+        io::Error::new(kind, why)
+    }
+}
+
+/// The host path of the file that `path` refers to on the system whose root
+/// directory is `root`.
+///
+/// Every symlink along the way is resolved within `root`. The final component need
+/// not exist, but the directories leading up to it must.
+///
+/// Errors caused by symlinks carry a `SymlinkError`.
+pub fn resolve<P: AsRef<Path>, Q: AsRef<Path>>(root: P, path: Q) -> io::Result<PathBuf> {
+    let root = root.as_ref();
+    let mut resolved = root.to_path_buf();
+    let mut depth = 0;
+    let mut links = 0;
+    let mut pending: Vec<OsString> = components(path.as_ref()).rev().collect();
+
+    while let Some(part) = pending.pop() {
+        if part == ".." {
+            if depth == 0 {
+                return Err(SymlinkError::Escape(path.as_ref().to_path_buf()).into());
+            }
+            resolved.pop();
+            depth -= 1;
+            continue;
+        }
+
+        resolved.push(&part);
+        depth += 1;
+
+        let metadata = match fs::symlink_metadata(&resolved) {
+            Ok(metadata) => metadata,
+            Err(why) if why.kind() == io::ErrorKind::NotFound && pending.is_empty() => break,
+            Err(why) => return Err(why),
+        };
+
+        if metadata.file_type().is_symlink() {
+            links += 1;
+            if links > MAX_SYMLINKS {
+                return Err(SymlinkError::Loop(path.as_ref().to_path_buf()).into());
+            }
+
+            let target = fs::read_link(&resolved)?;
+            resolved.pop();
+            depth -= 1;
+            if target.has_root() {
+                resolved = root.to_path_buf();
+                depth = 0;
+            }
+            pending.extend(components(&target).rev());
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Open the file that `path` refers to on the system whose root directory is `root`.
+pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(root: P, path: Q) -> io::Result<File> {
+    File::open(resolve(root, path)?)
+}
+
+/// The names along `path`, with `..` kept, as it depends on the symlinks before it.
+fn components(path: &Path) -> impl DoubleEndedIterator<Item = OsString> + '_ {
+    path.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_owned()),
+        Component::ParentDir => Some("..".into()),
+        Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn fixture() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("etc")).unwrap();
+        fs::create_dir_all(root.path().join("usr/lib")).unwrap();
+        fs::write(root.path().join("usr/lib/os-release"), "ID=guest\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn absolute_links_stay_in_root() {
+        let root = fixture();
+        symlink("/usr/lib/os-release", root.path().join("etc/os-release")).unwrap();
+        symlink("/usr/lib", root.path().join("lib")).unwrap();
+
+        let expected = root.path().join("usr/lib/os-release");
+        assert_eq!(resolve(root.path(), "/etc/os-release").unwrap(), expected);
+        assert_eq!(resolve(root.path(), "lib/os-release").unwrap(), expected);
+        assert_eq!(
+            fs::read_to_string(resolve(root.path(), "/etc/os-release").unwrap()).unwrap(),
+            "ID=guest\n"
+        );
+    }
+
+    #[test]
+    fn relative_links() {
+        let root = fixture();
+        symlink("../usr/lib/os-release", root.path().join("etc/os-release")).unwrap();
+        assert_eq!(
+            resolve(root.path(), "/etc/os-release").unwrap(),
+            root.path().join("usr/lib/os-release")
+        );
+        assert_eq!(
+            resolve(root.path(), "/etc/../usr/lib/missing").unwrap(),
+            root.path().join("usr/lib/missing")
+        );
+    }
+
+    #[test]
+    fn escapes_are_errors() {
+        let root = fixture();
+        symlink(
+            "../../../../etc/os-release",
+            root.path().join("etc/os-release"),
+        )
+        .unwrap();
+
+        let why = resolve(root.path(), "/etc/os-release").unwrap_err();
+        assert_eq!(why.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            why.get_ref().unwrap().downcast_ref::<SymlinkError>(),
+            Some(&SymlinkError::Escape("/etc/os-release".into()))
+        );
+        assert!(resolve(root.path(), "../etc/os-release").is_err());
+    }
+
+    #[test]
+    fn loops_are_errors() {
+        let root = fixture();
+        symlink("b", root.path().join("etc/a")).unwrap();
+        symlink("/etc/a", root.path().join("etc/b")).unwrap();
+
+        let why = resolve(root.path(), "/etc/a").unwrap_err();
+        assert_eq!(
+            why.get_ref().unwrap().downcast_ref::<SymlinkError>(),
+            Some(&SymlinkError::Loop("/etc/a".into()))
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::parser;
+use crate::OsRelease;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// A shell to write variable assignments for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    /// POSIX sh and its descendants, such as bash, dash, ksh and zsh.
+    Posix,
+    /// The fish shell.
+    Fish,
+}
+
+impl Shell {
+    /// Quote `value` so that this shell reads it back as a single literal word.
+    ///
+    /// Nothing inside the quotes is expanded, so the result is safe to `eval`.
+    pub fn quote(self, value: &str) -> String {
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('\'');
+        for c in value.chars() {
+            match (self, c) {
+                // POSIX single quotes cannot contain a quote, so close them, add an
+                // escaped quote, and reopen them.
+                (Shell::Posix, '\'') => quoted.push_str(r"'\''"),
+                (Shell::Fish, '\'' | '\\') => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                (_, c) => quoted.push(c),
+            }
+        }
+        quoted.push('\'');
+        quoted
+    }
+
+    /// An assignment of `value` to the shell variable `name`.
+    ///
+    /// `name` is not checked, and must be a valid variable name.
+    pub fn assignment(self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Posix => format!("{}={}", name, self.quote(value)),
+            Shell::Fish => format!("set -g {} {}", name, self.quote(value)),
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = UnknownShell;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "sh" | "posix" | "bash" | "dash" | "ksh" | "zsh" => Ok(Shell::Posix),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(UnknownShell(name.to_owned())),
+        }
+    }
+}
+
+/// The error returned when parsing the name of an unsupported shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownShell(String);
+
+impl fmt::Display for UnknownShell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unsupported shell `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownShell {}
+
+impl OsRelease {
+    /// Every key as a shell variable assignment, one per line, for a script to `eval`
+    /// instead of sourcing the os-release file.
+    ///
+    /// Each variable is named `prefix` followed by the key, as in `OS_ID='fedora'` for
+    /// a prefix of `OS_`. Keys that would not make valid variable names are left out.
+    pub fn to_shell(&self, shell: Shell, prefix: &str) -> String {
+        let mut script = String::new();
+        for (key, value) in self.entries() {
+            let name = match prefix {
+                "" => Cow::Borrowed(key),
+                prefix => Cow::Owned(format!("{}{}", prefix, key)),
+            };
+
+            if parser::is_valid_key(&name) {
+                script.push_str(&shell.assignment(&name, &value));
+                script.push('\n');
+            }
+        }
+        script
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn posix_quoting() {
+        assert_eq!(Shell::Posix.quote("fedora"), "'fedora'");
+        assert_eq!(Shell::Posix.quote(""), "''");
+        assert_eq!(Shell::Posix.quote("it's"), r"'it'\''s'");
+        assert_eq!(Shell::Posix.quote(r"$(id) `id` \n"), r"'$(id) `id` \n'");
+    }
+
+    #[test]
+    fn fish_quoting() {
+        assert_eq!(Shell::Fish.quote("fedora"), "'fedora'");
+        assert_eq!(Shell::Fish.quote("it's"), r"'it\'s'");
+        assert_eq!(Shell::Fish.quote(r"a\b $c"), r"'a\\b $c'");
+    }
+
+    #[test]
+    fn to_shell() {
+        let mut os_release = OsRelease {
+            id: Some("fedora".into()),
+            id_like: vec!["rhel".into(), "centos".into()],
+            name: Some("Don't Panic".into()),
+            ..OsRelease::default()
+        };
+        os_release.extra.insert("BAD;KEY".into(), "x".into());
+
+        assert_eq!(
+            os_release.to_shell(Shell::Posix, "OS_"),
+            "OS_NAME='Don'\\''t Panic'\nOS_ID='fedora'\nOS_ID_LIKE='rhel centos'\n"
+        );
+        assert_eq!(
+            os_release.to_shell(Shell::Fish, ""),
+            "set -g NAME 'Don\\'t Panic'\nset -g ID 'fedora'\nset -g ID_LIKE 'rhel centos'\n"
+        );
+        assert_eq!(os_release.to_shell(Shell::Posix, "1"), "");
+    }
+
+    #[test]
+    fn shell_names() {
+        assert_eq!("bash".parse(), Ok(Shell::Posix));
+        assert_eq!("fish".parse(), Ok(Shell::Fish));
+        assert!("cmd".parse::<Shell>().is_err());
+    }
+}
@@ -0,0 +1,422 @@
+use crate::OsRelease;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// A parsed `VERSION_ID`, which can be ordered and matched against a `VersionReq`.
+///
+/// A version is a dotted run of numbers, such as `18.04` or `38`, optionally
+/// followed by a suffix, such as the `_alpha20231219` of `3.19_alpha20231219`.
+/// Numbers compare numerically and missing trailing numbers count as zero, so
+/// `8.10 > 8.9` and `9 == 9.0`. A suffix usually marks a pre-release, which
+/// orders before the same version without one. Service packs and patch levels,
+/// whose suffixes are `sp`, `p`, `post` or `patch` and a number, such as the
+/// `-SP5` of `15-SP5` or the `_p1` of `1.2_p1`, instead order after it. Suffixes
+/// of the same kind compare by their text and then by their trailing number, so
+/// `15-SP10 > 15-SP5`.
+///
+/// Rolling releases, which have no `VERSION_ID`, are represented by
+/// `Version::rolling`, which orders after every numbered version.
+#[derive(Clone, Debug)]
+pub struct Version {
+    /// The text this was parsed from, or `None` for a rolling release.
+    text: Option<String>,
+    parts: Vec<u64>,
+    /// Byte offset of the suffix within `text`.
+    suffix: usize,
+}
+
+impl Version {
+    /// The version of a rolling release.
+    pub fn rolling() -> Version {
+        Version {
+            text: None,
+            parts: Vec::new(),
+            suffix: 0,
+        }
+    }
+
+    /// Whether this is the version of a rolling release.
+    pub fn is_rolling(&self) -> bool {
+        self.text.is_none()
+    }
+
+    /// The numeric components of the version, which are empty for a rolling release.
+    pub fn parts(&self) -> &[u64] {
+        &self.parts
+    }
+
+    /// The first numeric component.
+    pub fn major(&self) -> Option<u64> {
+        self.parts.first().copied()
+    }
+
+    /// The second numeric component.
+    pub fn minor(&self) -> Option<u64> {
+        self.parts.get(1).copied()
+    }
+
+    /// Whatever follows the numeric components, which is usually empty.
+    pub fn suffix(&self) -> &str {
+        self.text.as_deref().map_or("", |text| &text[self.suffix..])
+    }
+
+    /// What the suffix is ordered by.
+    fn suffix_key(&self) -> SuffixKey<'_> {
+        let suffix = self.suffix();
+        let label = suffix.trim_end_matches(|c: char| c.is_ascii_digit());
+        let word = label.trim_start_matches(['-', '_', '.', '+']);
+        let stage = match suffix {
+            "" => Ordering::Equal,
+            _ if POST_RELEASES
+                .iter()
+                .any(|post| word.eq_ignore_ascii_case(post)) =>
+            {
+                Ordering::Greater
+            }
+            _ => Ordering::Less,
+        };
+        let digits = suffix[label.len()..].trim_start_matches('0');
+
+        SuffixKey {
+            stage,
+            label,
+            number: (digits.len(), digits),
+        }
+    }
+
+    /// The numeric components without trailing zeros, so that `9` and `9.0` agree.
+    fn significant_parts(&self) -> &[u64] {
+        let len = self
+            .parts
+            .iter()
+            .rposition(|&part| part != 0)
+            .map_or(0, |i| i + 1);
+        &self.parts[..len]
+    }
+}
+
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+
+        loop {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let part = rest[..digits]
+                .parse()
+                .map_err(|_| ParseVersionError(text.to_owned()))?;
+            parts.push(part);
+            rest = &rest[digits..];
+
+            match rest.strip_prefix('.') {
+                Some(next) if next.starts_with(|c: char| c.is_ascii_digit()) => rest = next,
+                _ => break,
+            }
+        }
+
+        Ok(Version {
+            suffix: text.len() - rest.len(),
+            text: Some(text.to_owned()),
+            parts,
+        })
+    }
+}
+
+/// The words of suffixes that mark a release made after the version, not before it.
+const POST_RELEASES: [&str; 4] = ["sp", "p", "post", "patch"];
+
+/// The parts of a suffix, in the order that they are compared.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct SuffixKey<'a> {
+    /// `Less` for a pre-release, `Equal` for no suffix and `Greater` for a post-release.
+    stage: Ordering,
+    /// The suffix without its trailing number.
+    label: &'a str,
+    /// The trailing number without leading zeros, preceded by its length so that
+    /// numbers of any size compare numerically.
+    number: (usize, &'a str),
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.text.as_deref().unwrap_or("rolling"))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_rolling(), other.is_rolling()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => (),
+        }
+
+        self.significant_parts()
+            .cmp(other.significant_parts())
+            .then_with(|| self.suffix_key().cmp(&other.suffix_key()))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.is_rolling().hash(state);
+        self.significant_parts().hash(state);
+        self.suffix_key().hash(state);
+    }
+}
+
+/// Serializes as its `VERSION_ID`, or as `rolling` for a rolling release.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Version {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        match text.as_str() {
+            "rolling" => Ok(Version::rolling()),
+            text => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// The error returned when a string does not start with a version number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseVersionError(String);
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a valid version", self.0)
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+/// A set of conditions that a `Version` may satisfy, such as `>= 20.04` or `8.x`.
+///
+/// Conditions are separated by commas, and all of them must hold. Each is an
+/// operator of `=`, `==`, `!=`, `<`, `<=`, `>` or `>=` followed by a version, or a
+/// version on its own. A version on its own, optionally ending in `.x` or `.*`,
+/// matches every version that starts with the same numbers, so `8` and `8.x` both
+/// match `8`, `8.0` and `8.9`. As in comparisons, missing numbers count as zero,
+/// so `8.0` matches `8` and `8.0.5`, but not `8.1`.
+///
+/// A rolling release satisfies only `>`, `>=` and `!=` conditions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionReq {
+    comparators: Vec<(Op, Version)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Prefix,
+}
+
+impl VersionReq {
+    /// Whether `version` satisfies every condition.
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|(op, wanted)| match op {
+            Op::Eq => version == wanted,
+            Op::Ne => version != wanted,
+            Op::Lt => version < wanted,
+            Op::Le => version <= wanted,
+            Op::Gt => version > wanted,
+            Op::Ge => version >= wanted,
+            Op::Prefix => {
+                !version.is_rolling()
+                    && wanted
+                        .parts
+                        .iter()
+                        .enumerate()
+                        .all(|(i, part)| version.parts.get(i).copied().unwrap_or_default() == *part)
+                    && (wanted.suffix().is_empty() || version.suffix_key() == wanted.suffix_key())
+            }
+        })
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = ParseVersionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseVersionError(text.to_owned());
+        let mut comparators = Vec::new();
+
+        for condition in text.split(',') {
+            let condition = condition.trim();
+            let (op, version) = [
+                ("==", Op::Eq),
+                ("!=", Op::Ne),
+                ("<=", Op::Le),
+                (">=", Op::Ge),
+                ("=", Op::Eq),
+                ("<", Op::Lt),
+                (">", Op::Gt),
+            ]
+            .iter()
+            .find_map(|&(prefix, op)| condition.strip_prefix(prefix).map(|rest| (op, rest)))
+            .unwrap_or((Op::Prefix, condition));
+
+            let mut version = version.trim();
+            if op == Op::Prefix {
+                version = version
+                    .strip_suffix(".x")
+                    .or_else(|| version.strip_suffix(".*"))
+                    .unwrap_or(version);
+            }
+
+            let version = version.parse::<Version>().map_err(|_| invalid())?;
+            comparators.push((op, version));
+        }
+
+        Ok(VersionReq { comparators })
+    }
+}
+
+impl OsRelease {
+    /// The parsed `VERSION_ID`, which is `Version::rolling` if it is absent.
+    ///
+    /// Returns `None` if `VERSION_ID` does not start with a number.
+    pub fn release_version(&self) -> Option<Version> {
+        match self.version_id.as_deref() {
+            Some(version_id) => version_id.parse().ok(),
+            None => Some(Version::rolling()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        text.parse().unwrap()
+    }
+
+    fn req(text: &str) -> VersionReq {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(v("18.04").parts(), [18, 4]);
+        assert_eq!(v("18.04").to_string(), "18.04");
+        assert_eq!(v("38").major(), Some(38));
+        assert_eq!(v("38").minor(), None);
+        let alpine = v("3.19_alpha20231219");
+        assert_eq!(alpine.parts(), [3, 19]);
+        assert_eq!(alpine.suffix(), "_alpha20231219");
+        assert_eq!(v("15-SP5").suffix(), "-SP5");
+        assert_eq!(v("9.").suffix(), ".");
+        assert!("rawhide".parse::<Version>().is_err());
+        assert!("".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(v("8.10") > v("8.9"));
+        assert!(v("20.04") > v("18.04"));
+        assert!(v("38") > v("9.2"));
+        assert_eq!(v("9"), v("9.0"));
+        assert!(v("3.19_alpha1") < v("3.19"));
+        assert!(v("3.19_alpha1") < v("3.19_alpha2"));
+        assert!(v("3.19_alpha9") < v("3.19_alpha10"));
+        assert!(v("3.19_beta1") < v("3.19_rc1"));
+
+        assert!(v("15-SP5") > v("15"));
+        assert!(v("15-SP5") < v("15.1"));
+        assert!(v("15-SP10") > v("15-SP5"));
+        assert!(v("1.2_p1") > v("1.2"));
+        assert!(v("1.2_p1") > v("1.2_rc1"));
+        assert_eq!(v("15-SP05"), v("15-SP5"));
+        assert_eq!(v("15.0-SP5"), v("15-SP5"));
+        assert!(Version::rolling() > v("2023"));
+        assert_eq!(Version::rolling(), Version::rolling());
+    }
+
+    #[test]
+    fn requirements() {
+        assert!(req(">= 20.04").matches(&v("22.04")));
+        assert!(req(">=20.04").matches(&v("20.04")));
+        assert!(!req(">= 20.04").matches(&v("18.04")));
+        assert!(req("8.x").matches(&v("8.9")));
+        assert!(req("8.*").matches(&v("8")));
+        assert!(req("8").matches(&v("8.0")));
+        assert!(req("8.0").matches(&v("8")));
+        assert!(req("8.0").matches(&v("8.0.5")));
+        assert!(!req("8.0").matches(&v("8.1")));
+        assert!(req("15-SP5").matches(&v("15.0-SP5")));
+        assert!(req("15-SP5").matches(&v("15-SP05")));
+        assert!(!req("8.x").matches(&v("9.2")));
+        assert!(!req("8.x").matches(&v("80")));
+        assert!(req(">= 8, < 9").matches(&v("8.6")));
+        assert!(!req(">= 8, < 9").matches(&v("9.0")));
+        assert!(req("= 38").matches(&v("38")));
+        assert!(req("!= 38").matches(&v("39")));
+        assert!("> rawhide".parse::<VersionReq>().is_err());
+        assert!("".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn rolling_releases() {
+        let rolling = Version::rolling();
+        assert!(req(">= 20.04").matches(&rolling));
+        assert!(!req("< 20.04").matches(&rolling));
+        assert!(!req("8.x").matches(&rolling));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        assert_eq!(serde_json::to_string(&v("18.04")).unwrap(), r#""18.04""#);
+        assert_eq!(
+            serde_json::to_string(&Version::rolling()).unwrap(),
+            r#""rolling""#
+        );
+        let parsed: Version = serde_json::from_str(r#""3.19_alpha1""#).unwrap();
+        assert_eq!(parsed.suffix(), "_alpha1");
+        let parsed: Version = serde_json::from_str(r#""rolling""#).unwrap();
+        assert!(parsed.is_rolling());
+        assert!(serde_json::from_str::<Version>(r#""sid""#).is_err());
+    }
+
+    #[test]
+    fn os_release_version() {
+        let parse = |input: &str| OsRelease::from_iter(input.lines().map(String::from));
+        assert_eq!(parse("ID=arch").release_version(), Some(Version::rolling()));
+        assert_eq!(
+            parse("VERSION_ID=\"18.04\"").release_version(),
+            Some(v("18.04"))
+        );
+        assert_eq!(parse("VERSION_ID=sid").release_version(), None);
+    }
+}
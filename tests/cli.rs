@@ -0,0 +1,326 @@
+use std::process::{Command, Output};
+
+const FEDORA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fedora-rawhide-os-release");
+
+fn distro(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args(["--file", FEDORA])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = distro(args);
+    assert!(output.status.success(), "{:?} failed: {:?}", args, output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn get() {
+    assert_eq!(stdout(&["get", "VERSION_ID"]), "38\n");
+    assert_eq!(
+        stdout(&["get", "ID", "VARIANT"]),
+        "fedora\nWorkstation Edition\n"
+    );
+    assert_eq!(distro(&["get", "ID_LIKE"]).status.code(), Some(1));
+}
+
+#[test]
+fn all() {
+    let all = stdout(&[]);
+    assert!(all.starts_with("NAME=\"Fedora Linux\"\nID=fedora\n"));
+    assert_eq!(stdout(&["all"]), all);
+
+    let json = stdout(&["--json"]);
+    assert!(json.starts_with(r#"{"NAME":"Fedora Linux","ID":"fedora","#));
+    assert!(json.ends_with("}\n"));
+}
+
+#[test]
+fn json_escapes() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("os-release");
+    std::fs::write(&file, "NAME=\"Say \\\"hi\\\"\"\nID=test\tx\nFOO='a\\b'\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args(["--file", file.to_str().unwrap(), "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"NAME\":\"Say \\\"hi\\\"\",\"ID\":\"test\\tx\",\"FOO\":\"a\\\\b\"}\n"
+    );
+}
+
+#[test]
+fn tests_use_exit_status() {
+    assert_eq!(distro(&["is", "fedora"]).status.code(), Some(0));
+    assert_eq!(distro(&["is", "fedora-like"]).status.code(), Some(0));
+    assert_eq!(distro(&["is", "debian-like"]).status.code(), Some(1));
+    assert_eq!(distro(&["version", ">= 37"]).status.code(), Some(0));
+    assert_eq!(distro(&["version", "< 37"]).status.code(), Some(1));
+    assert_eq!(stdout(&["version"]), "38\n");
+}
+
+#[test]
+fn errors() {
+    let output = distro(&["version", ">= sid"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "distro: `>= sid` is not a valid version\n"
+    );
+
+    assert_eq!(distro(&["frobnicate"]).status.code(), Some(2));
+    assert_eq!(distro(&["--root", "/nonexistent"]).status.code(), Some(2));
+}
+
+#[test]
+fn export() {
+    let script = stdout(&["--export", "--prefix", "OS_"]);
+    assert!(script.starts_with("OS_NAME='Fedora Linux'\nOS_ID='fedora'\n"));
+
+    let fish = stdout(&["--export", "--shell", "fish"]);
+    assert!(fish.starts_with("set -g NAME 'Fedora Linux'\n"));
+
+    assert_eq!(
+        distro(&["--export", "--prefix", "OS-"]).status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        distro(&["--export", "--shell", "cmd"]).status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn export_evaluates_in_sh() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("os-release");
+    std::fs::write(&file, "NAME=\"It's \\$(touch pwned) \\`id\\`\"\nID=test\n").unwrap();
+
+    let script = Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args([
+            "--file",
+            file.to_str().unwrap(),
+            "--export",
+            "--prefix",
+            "OS_",
+        ])
+        .output()
+        .unwrap();
+    let script = String::from_utf8(script.stdout).unwrap();
+
+    let output = Command::new("sh")
+        .current_dir(dir.path())
+        .args([
+            "-c",
+            r#"eval "$1"; printf '%s|%s' "$OS_NAME" "$OS_ID""#,
+            "sh",
+            &script,
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "It's $(touch pwned) `id`|test"
+    );
+    assert!(!dir.path().join("pwned").exists());
+}
+
+#[test]
+fn legacy_fallback() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir(root.path().join("etc")).unwrap();
+    std::fs::write(root.path().join("etc/alpine-release"), "3.2.3\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args([
+            "--root",
+            root.path().to_str().unwrap(),
+            "get",
+            "ID",
+            "VERSION_ID",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "alpine\n3.2.3\n");
+}
+
+#[test]
+fn lsb_release() {
+    assert_eq!(
+        stdout(&["lsb_release", "-a"]),
+        "Distributor ID:\tFedora\nDescription:\tFedora Linux 38 (Workstation Edition Prerelease)\n\
+         Release:\t38\nCodename:\tn/a\n"
+    );
+    assert_eq!(stdout(&["lsb_release", "-sir"]), "Fedora\n38\n");
+
+    let output = distro(&["lsb_release"]);
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "No LSB modules are available.\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn invoked_as_lsb_release() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir(root.path().join("etc")).unwrap();
+    std::fs::write(
+        root.path().join("etc/os-release"),
+        "NAME=Ubuntu\nID=ubuntu\nVERSION_ID=22.04\nVERSION_CODENAME=jammy\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.path().join("etc/lsb-release"),
+        "DISTRIB_ID=Ubuntu\nDISTRIB_DESCRIPTION=\"Ubuntu 22.04.3 LTS\"\n",
+    )
+    .unwrap();
+
+    let lsb_release = root.path().join("lsb_release");
+    std::os::unix::fs::symlink(env!("CARGO_BIN_EXE_distro"), &lsb_release).unwrap();
+
+    let output = Command::new(&lsb_release).arg("--help").output().unwrap();
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Usage: lsb_release [OPTIONS]"));
+    let output = Command::new(&lsb_release).arg("-x").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args([
+            "--root",
+            root.path().to_str().unwrap(),
+            "lsb_release",
+            "-dc",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Description:\tUbuntu 22.04.3 LTS\nCodename:\tjammy\n"
+    );
+}
+
+#[test]
+fn eol() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("os-release");
+    std::fs::write(&file, "ID=fedora\nSUPPORT_END=2024-05-14\n").unwrap();
+    let eol = |date: &str| {
+        Command::new(env!("CARGO_BIN_EXE_distro"))
+            .args(["--file", file.to_str().unwrap(), "eol", date])
+            .output()
+            .unwrap()
+    };
+
+    let output = eol("2024-05-04");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "support ends on 2024-05-14 (10 days left)\n"
+    );
+
+    let output = eol("2024-05-14");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "support ended on 2024-05-14 (today)\n"
+    );
+
+    let output = eol("2024-05-15");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "support ended on 2024-05-14 (1 day ago)\n"
+    );
+
+    assert_eq!(eol("2024-5-15").status.code(), Some(2));
+}
+
+#[test]
+fn eol_lifecycle() {
+    // Fedora 38 does not set SUPPORT_END, so the built-in table is used. Its last
+    // day of support is 2024-05-21.
+    assert_eq!(
+        stdout(&["eol", "2024-05-01"]),
+        "support ends on 2024-05-22 (21 days left)\n"
+    );
+    assert_eq!(distro(&["eol", "2024-05-21"]).status.code(), Some(0));
+    assert_eq!(distro(&["eol", "2024-05-22"]).status.code(), Some(1));
+
+    let dir = tempfile::tempdir().unwrap();
+    let table = dir.path().join("lifecycle.tsv");
+    std::fs::write(&table, "fedora\t38\t-\t2023-04-18\t2030-01-01\n").unwrap();
+    let table = table.to_str().unwrap();
+    assert_eq!(
+        stdout(&["--lifecycle", table, "eol", "2030-01-01"]),
+        "support ends on 2030-01-02 (1 day left)\n"
+    );
+
+    std::fs::write(dir.path().join("os-release"), "ID=arch\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_distro"))
+        .args([
+            "--file",
+            dir.path().join("os-release").to_str().unwrap(),
+            "eol",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "end of support is unknown\n"
+    );
+}
+
+#[test]
+fn summary() {
+    let summary = |args: &[&str], vars: &[(&str, &str)]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_distro"))
+            .args(["--file", FEDORA])
+            .args(args)
+            .env_remove("NO_COLOR")
+            .envs(vars.iter().copied())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?} failed: {:?}", args, output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let name = "Fedora Linux 38 (Workstation Edition Prerelease)";
+    let plain = format!("OS: {} {}\n", name, std::env::consts::ARCH);
+
+    // Output that is not a terminal is only colored on request.
+    assert_eq!(summary(&["summary"], &[("COLORTERM", "truecolor")]), plain);
+    assert_eq!(summary(&["--color", "never", "summary"], &[]), plain);
+    assert_eq!(
+        summary(
+            &["--color", "always", "summary"],
+            &[("COLORTERM", "truecolor"), ("NO_COLOR", "1")]
+        ),
+        format!(
+            "OS: \x1b[0;38;2;60;110;180m{}\x1b[0m {}\n",
+            name,
+            std::env::consts::ARCH
+        )
+    );
+    assert_eq!(
+        summary(
+            &["--color", "always", "summary"],
+            &[("COLORTERM", ""), ("TERM", "xterm-256color")]
+        ),
+        format!(
+            "OS: \x1b[0;38;5;61m{}\x1b[0m {}\n",
+            name,
+            std::env::consts::ARCH
+        )
+    );
+    assert_eq!(
+        distro(&["--color", "sometimes", "summary"]).status.code(),
+        Some(2)
+    );
+}